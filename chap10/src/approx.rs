@@ -0,0 +1,115 @@
+use crate::{
+    euler_angles::EulerAngles,
+    matrix::{Matrix3x4, RotationMatrix},
+    quaternion::Quaternion,
+    vector::V3,
+};
+
+pub const DEFAULT_EPSILON: f64 = 1e-9;
+
+// 浮動小数点の誤差を許した比較。テストで変換結果を確かめるのに使う
+pub trait ApproxEq {
+    // 差の絶対値がepsilon以下
+    fn abs_diff_eq(&self, other: &Self, epsilon: f64) -> bool;
+
+    // 絶対誤差がepsilon以下、または大きい方の値に対する相対誤差がmax_relative以下
+    fn relative_eq(&self, other: &Self, epsilon: f64, max_relative: f64) -> bool;
+
+    fn approx_eq(&self, other: &Self) -> bool {
+        self.relative_eq(other, DEFAULT_EPSILON, DEFAULT_EPSILON)
+    }
+}
+
+impl ApproxEq for f64 {
+    fn abs_diff_eq(&self, other: &Self, epsilon: f64) -> bool {
+        (self - other).abs() <= epsilon
+    }
+
+    fn relative_eq(&self, other: &Self, epsilon: f64, max_relative: f64) -> bool {
+        if self == other {
+            return true;
+        }
+
+        let diff = (self - other).abs();
+        if diff <= epsilon {
+            return true;
+        }
+
+        diff <= self.abs().max(other.abs()) * max_relative
+    }
+}
+
+// 成分を並べて、すべての成分がf64として近いかで判定する
+// 四元数は成分で比べるので、qと-qは別物として扱う(同じ回転かはapprox_eq_rotationで)
+macro_rules! impl_approx_eq {
+    ($t:ty, |$v:ident| $components:expr) => {
+        impl ApproxEq for $t {
+            fn abs_diff_eq(&self, other: &Self, epsilon: f64) -> bool {
+                let components = |$v: &$t| $components;
+                components(self)
+                    .into_iter()
+                    .zip(components(other))
+                    .all(|(a, b)| a.abs_diff_eq(&b, epsilon))
+            }
+
+            fn relative_eq(&self, other: &Self, epsilon: f64, max_relative: f64) -> bool {
+                let components = |$v: &$t| $components;
+                components(self)
+                    .into_iter()
+                    .zip(components(other))
+                    .all(|(a, b)| a.relative_eq(&b, epsilon, max_relative))
+            }
+        }
+    };
+}
+
+impl_approx_eq!(V3, |v| v.into_iter());
+impl_approx_eq!(Quaternion, |q| [q.w, q.x, q.y, q.z]);
+impl_approx_eq!(EulerAngles, |e| [e.heading, e.pitch, e.bank]);
+impl_approx_eq!(Matrix3x4, |m| m.elements());
+impl_approx_eq!(RotationMatrix, |m| m.elements());
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{matrix::Axis, v3};
+
+    #[test]
+    fn scalars() {
+        assert!(1.0.abs_diff_eq(&(1.0 + 1e-10), DEFAULT_EPSILON));
+        assert!(!1.0.abs_diff_eq(&1.1, DEFAULT_EPSILON));
+        assert!(1e12.relative_eq(&(1e12 + 1.0), DEFAULT_EPSILON, 1e-9));
+        assert!(!1e12.abs_diff_eq(&(1e12 + 1.0), DEFAULT_EPSILON));
+    }
+
+    #[test]
+    fn vectors_and_rotations() {
+        let a = v3!(1.0, 2.0, 3.0);
+        assert!(a.approx_eq(&(a + v3!(1e-12, 0.0, -1e-12))));
+        assert!(!a.approx_eq(&v3!(1.0, 2.0, 3.1)));
+
+        let q = Quaternion {
+            w: 0.5f64.sqrt(),
+            x: 0.0,
+            y: 0.5f64.sqrt(),
+            z: 0.0,
+        };
+        let m = RotationMatrix::from_axis_vectors(
+            v3!(0.0, 0.0, -1.0),
+            v3!(0.0, 1.0, 0.0),
+            v3!(1.0, 0.0, 0.0),
+        );
+        let back = RotationMatrix::from_axis_vectors(
+            v3!(1e-12, 0.0, -1.0),
+            v3!(0.0, 1.0, 0.0),
+            v3!(1.0, 0.0, 0.0),
+        );
+        assert!(q.approx_eq(&Quaternion {
+            w: q.w + 1e-12,
+            ..q
+        }));
+        assert!(!q.approx_eq(&Quaternion { y: -q.y, ..q }));
+        assert!(m.approx_eq(&back));
+        assert!(!m.approx_eq(&RotationMatrix::from_axes(Axis::Z, Axis::Y, Axis::NegX)));
+    }
+}
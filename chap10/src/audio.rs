@@ -0,0 +1,119 @@
+use std::f64::consts::FRAC_PI_4;
+
+use crate::vector::V3;
+
+// 逆距離減衰。min_distance以内は1、max_distanceより先は一定
+pub fn inverse_distance_attenuation(
+    distance: f64,
+    min_distance: f64,
+    max_distance: f64,
+    rolloff: f64,
+) -> f64 {
+    let d = distance.clamp(min_distance, max_distance);
+    min_distance / (min_distance + rolloff * (d - min_distance))
+}
+
+// 逆二乗減衰。近すぎると発散するのでmin_distanceでクランプする
+pub fn inverse_square_attenuation(distance: f64, min_distance: f64, max_distance: f64) -> f64 {
+    let d = distance.clamp(min_distance, max_distance);
+    let r = min_distance / d;
+    r * r
+}
+
+// min_distanceで1、max_distanceで0になる線形減衰
+pub fn linear_attenuation(distance: f64, min_distance: f64, max_distance: f64) -> f64 {
+    if max_distance <= min_distance {
+        return if distance <= min_distance { 1.0 } else { 0.0 };
+    }
+
+    1.0 - ((distance - min_distance) / (max_distance - min_distance)).clamp(0.0, 1.0)
+}
+
+// ドップラー効果によるピッチの倍率 (c + v_listener) / (c - v_emitter)
+// 速度は互いに近づく向きを正とし、音速を超えないようにクランプする
+pub fn doppler_factor(
+    emitter_position: V3,
+    emitter_velocity: V3,
+    listener_position: V3,
+    listener_velocity: V3,
+    speed_of_sound: f64,
+) -> f64 {
+    let offset = listener_position - emitter_position;
+    let distance = offset.mag();
+    if distance == 0.0 {
+        return 1.0;
+    }
+    let dir = offset / distance;
+
+    let limit = speed_of_sound * 0.99;
+    let v_emitter = emitter_velocity.dot(&dir).clamp(-limit, limit);
+    let v_listener = (-listener_velocity.dot(&dir)).clamp(-limit, limit);
+
+    (speed_of_sound + v_listener) / (speed_of_sound - v_emitter)
+}
+
+// 聴き手の右方向に対する音源の向き。-1: 左, 0: 正面/背面, 1: 右
+pub fn stereo_pan(listener_position: V3, listener_right: V3, source_position: V3) -> f64 {
+    let offset = source_position - listener_position;
+    let distance = offset.mag();
+    if distance == 0.0 {
+        return 0.0;
+    }
+
+    (offset / distance).dot(&listener_right).clamp(-1.0, 1.0)
+}
+
+// 等パワーパンニングの左右のゲイン
+pub fn constant_power_gains(pan: f64) -> (f64, f64) {
+    let angle = (pan.clamp(-1.0, 1.0) + 1.0) * FRAC_PI_4;
+    (angle.cos(), angle.sin())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v3;
+
+    #[test]
+    fn attenuation_curves() {
+        assert_eq!(inverse_distance_attenuation(0.5, 1.0, 100.0, 1.0), 1.0);
+        assert_eq!(inverse_distance_attenuation(4.0, 1.0, 100.0, 1.0), 0.25);
+        assert_eq!(inverse_square_attenuation(2.0, 1.0, 100.0), 0.25);
+        assert!((inverse_square_attenuation(1000.0, 1.0, 10.0) - 0.01).abs() < 1e-12);
+        assert_eq!(linear_attenuation(5.5, 1.0, 10.0), 0.5);
+    }
+
+    #[test]
+    fn doppler() {
+        let still = v3!(0.0, 0.0, 0.0);
+        let approaching = doppler_factor(
+            still,
+            v3!(10.0, 0.0, 0.0),
+            v3!(100.0, 0.0, 0.0),
+            still,
+            340.0,
+        );
+        assert!((approaching - 340.0 / 330.0).abs() < 1e-12);
+
+        let receding = doppler_factor(
+            still,
+            v3!(-10.0, 0.0, 0.0),
+            v3!(100.0, 0.0, 0.0),
+            still,
+            340.0,
+        );
+        assert!(receding < 1.0);
+    }
+
+    #[test]
+    fn panning() {
+        let right = v3!(1.0, 0.0, 0.0);
+        let origin = v3!(0.0, 0.0, 0.0);
+        assert_eq!(stereo_pan(origin, right, v3!(5.0, 0.0, 0.0)), 1.0);
+        assert_eq!(stereo_pan(origin, right, v3!(0.0, 0.0, 5.0)), 0.0);
+
+        let (l, r) = constant_power_gains(0.0);
+        assert!((l - r).abs() < 1e-12);
+        assert!((l * l + r * r - 1.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,148 @@
+use crate::vector::V3;
+
+// +yが上のときの地表の重力加速度 (m/s^2)
+pub const EARTH_GRAVITY: V3 = V3 {
+    x: 0.0,
+    y: -9.81,
+    z: 0.0,
+};
+
+// 初期位置p0、初速v0、一定の加速度gの放物線 p(t) = p0 + v0 t + g t^2 / 2
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BallisticArc {
+    pub origin: V3,
+    pub velocity: V3,
+    pub gravity: V3,
+}
+
+impl BallisticArc {
+    pub fn new(origin: V3, velocity: V3, gravity: V3) -> Self {
+        Self {
+            origin,
+            velocity,
+            gravity,
+        }
+    }
+
+    pub fn position(&self, t: f64) -> V3 {
+        self.origin + self.velocity * t + self.gravity * (0.5 * t * t)
+    }
+
+    pub fn velocity_at(&self, t: f64) -> V3 {
+        self.velocity + self.gravity * t
+    }
+
+    // [0, duration] を等間隔にcount点。両端を含む
+    pub fn samples(&self, duration: f64, count: usize) -> Vec<V3> {
+        match count {
+            0 => Vec::new(),
+            1 => vec![self.origin],
+            _ => (0..count)
+                .map(|i| self.position(duration * i as f64 / (count - 1) as f64))
+                .collect(),
+        }
+    }
+
+    // [0, duration] の軌跡のAABB (min, max)
+    // 各軸は t の2次式なので、両端と頂点 (速度が0になる時刻) だけを調べればよい
+    pub fn aabb(&self, duration: f64) -> (V3, V3) {
+        let start = self.origin;
+        let end = self.position(duration);
+        let mut min = start.min(&end);
+        let mut max = start.max(&end);
+
+        for axis in 0..3 {
+            let g = self.gravity[axis];
+            if g == 0.0 {
+                continue;
+            }
+
+            let t = -self.velocity[axis] / g;
+            if t > 0.0 && t < duration {
+                let c = self.position(t)[axis];
+                min[axis] = min[axis].min(c);
+                max[axis] = max[axis].max(c);
+            }
+        }
+
+        (min, max)
+    }
+
+    // 平面 normal・p = distance と交わる時刻。小さい順に最大2つ(負の時刻も含む)
+    // 平面に沿って動き続けるなど交点が決まらないときは空
+    pub fn plane_intersections(&self, normal: V3, distance: f64) -> Vec<f64> {
+        let a = 0.5 * normal.dot(&self.gravity);
+        let b = normal.dot(&self.velocity);
+        let c = normal.dot(&self.origin) - distance;
+
+        if a.abs() < 1e-12 {
+            if b.abs() < 1e-12 {
+                return Vec::new();
+            }
+            return vec![-c / b];
+        }
+
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            return Vec::new();
+        }
+
+        // 桁落ちを避ける形の解の公式
+        let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+        let (t0, t1) = if q == 0.0 { (0.0, 0.0) } else { (q / a, c / q) };
+
+        vec![t0.min(t1), t0.max(t1)]
+    }
+
+    // t >= 0 で最初に平面と交わる時刻
+    pub fn first_plane_hit(&self, normal: V3, distance: f64) -> Option<f64> {
+        self.plane_intersections(normal, distance)
+            .into_iter()
+            .find(|t| *t >= 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v3;
+
+    fn arc() -> BallisticArc {
+        BallisticArc::new(
+            v3!(0.0, 0.0, 0.0),
+            v3!(3.0, 10.0, 0.0),
+            v3!(0.0, -10.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn samples_and_bounds() {
+        let arc = arc();
+        let samples = arc.samples(2.0, 5);
+        assert_eq!(samples.len(), 5);
+        assert_eq!(samples[0], v3!(0.0, 0.0, 0.0));
+        assert_eq!(samples[4], v3!(6.0, 0.0, 0.0));
+
+        // 頂点は t = 1 で高さ5
+        let (min, max) = arc.aabb(2.0);
+        assert_eq!(min, v3!(0.0, 0.0, 0.0));
+        assert_eq!(max, v3!(6.0, 5.0, 0.0));
+    }
+
+    #[test]
+    fn plane_hits() {
+        let arc = arc();
+        let up = v3!(0.0, 1.0, 0.0);
+
+        let times = arc.plane_intersections(up, 3.2);
+        assert_eq!(times.len(), 2);
+        assert!((times[0] - 0.4).abs() < 1e-12 && (times[1] - 1.6).abs() < 1e-12);
+
+        assert!(arc.plane_intersections(up, 6.0).is_empty());
+        assert!((arc.first_plane_hit(up, -15.0).unwrap() - 3.0).abs() < 1e-12);
+
+        // 重力と直交する平面は1回だけ
+        let x_times = arc.plane_intersections(v3!(1.0, 0.0, 0.0), 4.5);
+        assert_eq!(x_times, vec![1.5]);
+    }
+}
@@ -0,0 +1,70 @@
+use crate::vector::V3;
+
+// 三角形abcに対するpの重心座標 (u, v, w)。p = u * a + v * b + w * c, u + v + w = 1
+// pが三角形の平面上にないときは平面に射影した点の座標になる
+// 面積がほぼ0の潰れた三角形ではNone
+pub fn barycentric(p: V3, a: V3, b: V3, c: V3) -> Option<(f64, f64, f64)> {
+    let e0 = b - a;
+    let e1 = c - a;
+    let e2 = p - a;
+
+    let d00 = e0.dot(&e0);
+    let d01 = e0.dot(&e1);
+    let d11 = e1.dot(&e1);
+    let d20 = e2.dot(&e0);
+    let d21 = e2.dot(&e1);
+
+    // |e0 × e1|^2。辺の長さに対して相対的に判定する
+    let denom = d00 * d11 - d01 * d01;
+    if denom <= 1e-12 * d00 * d11 {
+        return None;
+    }
+
+    let v = (d11 * d20 - d01 * d21) / denom;
+    let w = (d00 * d21 - d01 * d20) / denom;
+
+    Some((1.0 - v - w, v, w))
+}
+
+// 重心座標から点を求める
+pub fn from_barycentric(a: V3, b: V3, c: V3, (u, v, w): (f64, f64, f64)) -> V3 {
+    a * u + b * v + c * w
+}
+
+// 重心座標がすべて0以上なら三角形の内側(辺上を含む)
+pub fn is_inside(coords: (f64, f64, f64)) -> bool {
+    coords.0 >= 0.0 && coords.1 >= 0.0 && coords.2 >= 0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v3;
+
+    #[test]
+    fn roundtrip() {
+        let a = v3!(0.0, 0.0, 0.0);
+        let b = v3!(4.0, 0.0, 1.0);
+        let c = v3!(0.0, 3.0, -1.0);
+
+        assert_eq!(barycentric(a, a, b, c), Some((1.0, 0.0, 0.0)));
+
+        let coords = (0.2, 0.3, 0.5);
+        let p = from_barycentric(a, b, c, coords);
+        let (u, v, w) = barycentric(p, a, b, c).unwrap();
+        assert!((u - 0.2).abs() < 1e-12 && (v - 0.3).abs() < 1e-12 && (w - 0.5).abs() < 1e-12);
+        assert!(is_inside((u, v, w)));
+        assert!(!is_inside(
+            barycentric(v3!(5.0, 5.0, 0.0), a, b, c).unwrap()
+        ));
+    }
+
+    #[test]
+    fn degenerate_triangle() {
+        let a = v3!(0.0, 0.0, 0.0);
+        let b = v3!(1.0, 1.0, 1.0);
+        let c = v3!(2.0, 2.0, 2.0);
+        assert_eq!(barycentric(a, a, b, c), None);
+        assert_eq!(barycentric(a, a, a, a), None);
+    }
+}
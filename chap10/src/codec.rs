@@ -0,0 +1,223 @@
+use std::fmt;
+
+use crate::{quaternion::Quaternion, se3::Se3, v3, vector::V3};
+
+// リプレイやネットワークのスナップショットを、実行環境によらずビット単位で再現するための形式
+// [バージョン: u8][要素数: u32 LE][要素...]。f64はビット列をそのままリトルエンディアンで書く
+pub const FORMAT_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    UnexpectedEnd,
+    UnsupportedVersion(u8),
+    // 最後の要素の後に余ったバイト数
+    TrailingBytes(usize),
+    // 可変長整数が64ビットに収まらない
+    InvalidVarint,
+    // smallest-threeの最大成分の番号が0..4の範囲外
+    InvalidComponentIndex(u8),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnexpectedEnd => write!(f, "unexpected end of input"),
+            DecodeError::UnsupportedVersion(v) => write!(f, "unsupported format version {}", v),
+            DecodeError::TrailingBytes(n) => write!(f, "{} trailing bytes after last item", n),
+            DecodeError::InvalidVarint => write!(f, "varint does not fit in 64 bits"),
+            DecodeError::InvalidComponentIndex(i) => {
+                write!(f, "invalid quaternion component index {}", i)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+// フィールドの順序は固定。変えるときはFORMAT_VERSIONを上げる
+pub trait Encode {
+    fn encode(&self, out: &mut Vec<u8>);
+}
+
+pub trait Decode: Sized {
+    // 読んだ分だけinputを進める
+    fn decode(input: &mut &[u8]) -> Result<Self, DecodeError>;
+}
+
+pub(crate) fn take<'a>(input: &mut &'a [u8], n: usize) -> Result<&'a [u8], DecodeError> {
+    if input.len() < n {
+        return Err(DecodeError::UnexpectedEnd);
+    }
+
+    let (head, rest) = input.split_at(n);
+    *input = rest;
+    Ok(head)
+}
+
+impl Encode for f64 {
+    fn encode(&self, out: &mut Vec<u8>) {
+        // NaNのペイロードや-0.0も保つためにビット列で書く
+        out.extend_from_slice(&self.to_bits().to_le_bytes());
+    }
+}
+
+impl Decode for f64 {
+    fn decode(input: &mut &[u8]) -> Result<Self, DecodeError> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(take(input, 8)?);
+
+        Ok(f64::from_bits(u64::from_le_bytes(bytes)))
+    }
+}
+
+impl Encode for V3 {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.x.encode(out);
+        self.y.encode(out);
+        self.z.encode(out);
+    }
+}
+
+impl Decode for V3 {
+    fn decode(input: &mut &[u8]) -> Result<Self, DecodeError> {
+        Ok(v3!(
+            f64::decode(input)?,
+            f64::decode(input)?,
+            f64::decode(input)?,
+        ))
+    }
+}
+
+impl Encode for Quaternion {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.w.encode(out);
+        self.x.encode(out);
+        self.y.encode(out);
+        self.z.encode(out);
+    }
+}
+
+impl Decode for Quaternion {
+    fn decode(input: &mut &[u8]) -> Result<Self, DecodeError> {
+        Ok(Quaternion {
+            w: f64::decode(input)?,
+            x: f64::decode(input)?,
+            y: f64::decode(input)?,
+            z: f64::decode(input)?,
+        })
+    }
+}
+
+// 剛体変換は回転、平行移動の順
+impl Encode for Se3 {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.rotation.encode(out);
+        self.translation.encode(out);
+    }
+}
+
+impl Decode for Se3 {
+    fn decode(input: &mut &[u8]) -> Result<Self, DecodeError> {
+        let rotation = Quaternion::decode(input)?;
+        let translation = V3::decode(input)?;
+
+        Ok(Se3::new(rotation, translation))
+    }
+}
+
+pub fn encode_stream<T: Encode>(items: &[T]) -> Vec<u8> {
+    let mut out = vec![FORMAT_VERSION];
+    out.extend_from_slice(&(items.len() as u32).to_le_bytes());
+    for item in items {
+        item.encode(&mut out);
+    }
+
+    out
+}
+
+pub fn decode_stream<T: Decode>(bytes: &[u8]) -> Result<Vec<T>, DecodeError> {
+    let mut input = bytes;
+
+    let version = take(&mut input, 1)?[0];
+    if version != FORMAT_VERSION {
+        return Err(DecodeError::UnsupportedVersion(version));
+    }
+
+    let mut count = [0; 4];
+    count.copy_from_slice(take(&mut input, 4)?);
+    let count = u32::from_le_bytes(count) as usize;
+
+    let items = (0..count)
+        .map(|_| T::decode(&mut input))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if !input.is_empty() {
+        return Err(DecodeError::TrailingBytes(input.len()));
+    }
+
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip_is_bit_exact() {
+        let nan = f64::from_bits(0x7ff8_0000_dead_beef);
+        let points = vec![
+            v3!(1.0, -0.0, 0.1 + 0.2),
+            v3!(nan, f64::MIN_POSITIVE, 1e300),
+        ];
+
+        let bytes = encode_stream(&points);
+        assert_eq!(bytes.len(), 1 + 4 + 2 * 24);
+
+        let decoded: Vec<V3> = decode_stream(&bytes).unwrap();
+        for (a, b) in points.iter().zip(&decoded) {
+            for (x, y) in a.into_iter().zip(*b) {
+                assert_eq!(x.to_bits(), y.to_bits());
+            }
+        }
+    }
+
+    #[test]
+    fn poses() {
+        let pose = Se3::new(
+            Quaternion {
+                w: 0.5,
+                x: 0.5,
+                y: -0.5,
+                z: 0.5,
+            },
+            v3!(1.0, 2.0, 3.0),
+        );
+        let decoded: Vec<Se3> = decode_stream(&encode_stream(&[pose])).unwrap();
+        assert_eq!(decoded[0].translation, pose.translation);
+        assert_eq!(decoded[0].rotation.y, -0.5);
+    }
+
+    #[test]
+    fn errors() {
+        let bytes = encode_stream(&[v3!(1.0, 2.0, 3.0)]);
+
+        assert_eq!(
+            decode_stream::<V3>(&bytes[..bytes.len() - 1]),
+            Err(DecodeError::UnexpectedEnd)
+        );
+
+        let mut newer = bytes.clone();
+        newer[0] = FORMAT_VERSION + 1;
+        assert_eq!(
+            decode_stream::<V3>(&newer),
+            Err(DecodeError::UnsupportedVersion(FORMAT_VERSION + 1))
+        );
+
+        let mut longer = bytes;
+        longer.push(0);
+        assert_eq!(
+            decode_stream::<V3>(&longer),
+            Err(DecodeError::TrailingBytes(1))
+        );
+    }
+}
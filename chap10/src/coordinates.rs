@@ -0,0 +1,257 @@
+use std::f64::consts::PI;
+
+use crate::{
+    utils::{GameMath, PI2, PI_OVER_2},
+    v3,
+    vector::V3,
+    vector2::V2,
+};
+
+// 2次元の極座標。x = r cos(theta), y = r sin(theta)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Polar2 {
+    pub r: f64,
+    pub theta: f64,
+}
+
+// 球面座標。本と同じく +y が上、+z が前方、pitchは下向きが正
+// x = r cos(pitch) sin(heading), y = -r sin(pitch), z = r cos(pitch) cos(heading)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spherical {
+    pub r: f64,
+    pub heading: f64,
+    pub pitch: f64,
+}
+
+// 円柱座標。xz平面の極座標と高さy
+// x = r cos(theta), y = y, z = r sin(theta)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cylindrical {
+    pub r: f64,
+    pub theta: f64,
+    pub y: f64,
+}
+
+impl Polar2 {
+    pub fn new(r: f64, theta: f64) -> Self {
+        Polar2 { r, theta }
+    }
+
+    pub fn from_v2(v: V2) -> Self {
+        let mut result = Polar2::new(v.mag(), v.y.atan2(v.x));
+        result.canonize();
+        result
+    }
+
+    pub fn to_v2(&self) -> V2 {
+        let (s, c) = self.theta.sin_cos();
+
+        V2::new(self.r * c, self.r * s)
+    }
+
+    // r >= 0, theta [-PI, PI) の正準形にする。原点ではthetaを0にする
+    pub fn canonize(&mut self) {
+        if self.r == 0.0 {
+            self.theta = 0.0;
+            return;
+        }
+
+        if self.r < 0.0 {
+            self.r = -self.r;
+            self.theta += PI;
+        }
+
+        self.theta = self.theta.wrap_pi();
+    }
+
+    // 半径は線形に、角度は短い方の向きに回って補間する
+    pub fn lerp(&self, other: &Polar2, t: f64) -> Polar2 {
+        let mut a = *self;
+        let mut b = *other;
+        a.canonize();
+        b.canonize();
+
+        // 原点側の角度は意味を持たないので相手に合わせる
+        if a.r == 0.0 {
+            a.theta = b.theta;
+        } else if b.r == 0.0 {
+            b.theta = a.theta;
+        }
+
+        let delta = (b.theta - a.theta).wrap_pi();
+        let mut result = Polar2::new(a.r + (b.r - a.r) * t, a.theta + delta * t);
+        result.canonize();
+        result
+    }
+}
+
+impl Spherical {
+    pub fn new(r: f64, heading: f64, pitch: f64) -> Self {
+        Spherical { r, heading, pitch }
+    }
+
+    // 返り値は正準形
+    pub fn from_v3(v: V3) -> Self {
+        let r = v.mag();
+        if r == 0.0 {
+            return Spherical::new(0.0, 0.0, 0.0);
+        }
+
+        let mut result = Spherical::new(r, v.x.atan2(v.z), (-v.y / r).clamp(-1.0, 1.0).asin());
+        result.canonize();
+        result
+    }
+
+    pub fn to_v3(&self) -> V3 {
+        let (sp, cp) = self.pitch.sin_cos();
+        let (sh, ch) = self.heading.sin_cos();
+
+        v3!(self.r * cp * sh, -self.r * sp, self.r * cp * ch)
+    }
+
+    // r >= 0, heading [-PI, PI), pitch [-PI/2, PI/2] の正準形にする
+    // 原点ではすべての角度を0に、真上と真下ではheadingを0にする
+    pub fn canonize(&mut self) {
+        if self.r == 0.0 {
+            self.heading = 0.0;
+            self.pitch = 0.0;
+            return;
+        }
+
+        // 負の半径は反対側を向く
+        if self.r < 0.0 {
+            self.r = -self.r;
+            self.heading += PI;
+            self.pitch = -self.pitch;
+        }
+
+        // pitchを[-PI/2, PI/2]に。範囲外なら裏側から回り込んだ向き
+        if self.pitch.abs() > PI_OVER_2 {
+            let p = self.pitch + PI_OVER_2;
+            let p = p - (p / PI2).floor() * PI2;
+            if p > PI {
+                self.heading += PI;
+                self.pitch = 3.0 * PI_OVER_2 - p;
+            } else {
+                self.pitch = p - PI_OVER_2;
+            }
+        }
+
+        // 真上と真下ではheadingが意味を持たない。極のわずかに手前ではheadingで位置が変わるので残す
+        if self.pitch.abs() == PI_OVER_2 {
+            self.heading = 0.0;
+        } else {
+            self.heading = self.heading.wrap_pi();
+        }
+    }
+}
+
+impl Cylindrical {
+    pub fn new(r: f64, theta: f64, y: f64) -> Self {
+        Cylindrical { r, theta, y }
+    }
+
+    pub fn from_v3(v: V3) -> Self {
+        let mut result = Cylindrical::new((v.x * v.x + v.z * v.z).sqrt(), v.z.atan2(v.x), v.y);
+        result.canonize();
+        result
+    }
+
+    pub fn to_v3(&self) -> V3 {
+        let (s, c) = self.theta.sin_cos();
+
+        v3!(self.r * c, self.y, self.r * s)
+    }
+
+    // r >= 0, theta [-PI, PI) の正準形にする。軸上ではthetaを0にする
+    pub fn canonize(&mut self) {
+        if self.r == 0.0 {
+            self.theta = 0.0;
+            return;
+        }
+
+        if self.r < 0.0 {
+            self.r = -self.r;
+            self.theta += PI;
+        }
+
+        self.theta = self.theta.wrap_pi();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: V3, b: V3) -> bool {
+        (a - b).mag() < 1e-9
+    }
+
+    #[test]
+    fn polar2() {
+        let v = V2::new(-1.0, -1.0);
+        let p = Polar2::from_v2(v);
+        assert!((p.r - 2.0f64.sqrt()).abs() < 1e-12);
+        assert!((p.to_v2() - v).mag() < 1e-12);
+
+        let mut q = Polar2::new(-1.0, 0.0);
+        q.canonize();
+        assert!((q.to_v2() - V2::new(-1.0, 0.0)).mag() < 1e-12 && q.r > 0.0);
+
+        // 170度と-170度の間は180度を通る
+        let a = Polar2::new(1.0, 170f64.to_radians());
+        let b = Polar2::new(3.0, -170f64.to_radians());
+        let mid = a.lerp(&b, 0.5);
+        assert!((mid.r - 2.0).abs() < 1e-12);
+        assert!((mid.to_v2() - V2::new(-2.0, 0.0)).mag() < 1e-12);
+    }
+
+    #[test]
+    fn spherical_roundtrip() {
+        for v in [
+            v3!(1.0, 2.0, 3.0),
+            v3!(-4.0, -1.0, -0.5),
+            v3!(0.0, 5.0, 0.0),
+            v3!(0.0, 0.0, 0.0),
+            // 極のすぐそば
+            v3!(1e-4, 1.0, 0.0),
+            v3!(0.0, -1.0, -1e-6),
+        ] {
+            assert!(close(Spherical::from_v3(v).to_v3(), v));
+        }
+
+        // 前方は heading = pitch = 0、真上はpitchが負
+        assert_eq!(
+            Spherical::from_v3(v3!(0.0, 0.0, 2.0)),
+            Spherical::new(2.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            Spherical::from_v3(v3!(0.0, 2.0, 0.0)),
+            Spherical::new(2.0, 0.0, -PI_OVER_2)
+        );
+    }
+
+    #[test]
+    fn spherical_canonize() {
+        let mut s = Spherical::new(-2.0, 0.5, 2.5);
+        let before = s.to_v3();
+        s.canonize();
+
+        assert!(s.r > 0.0);
+        assert!(s.pitch.abs() <= PI_OVER_2);
+        assert!(s.heading >= -PI && s.heading < PI);
+        assert!(close(s.to_v3(), before));
+    }
+
+    #[test]
+    fn cylindrical() {
+        let v = v3!(-1.0, 2.0, 3.0);
+        assert!(close(Cylindrical::from_v3(v).to_v3(), v));
+
+        let mut c = Cylindrical::new(-2.0, 7.0, 1.0);
+        let before = c.to_v3();
+        c.canonize();
+        assert!(c.r > 0.0 && c.theta.abs() <= PI);
+        assert!(close(c.to_v3(), before));
+    }
+}
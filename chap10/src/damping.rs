@@ -0,0 +1,57 @@
+use crate::{quaternion::Quaternion, vector::V3};
+
+// 指数減衰による補間係数。smoothingは1秒あたりの減衰率
+// lerp(a, b, 定数) と違い、dtの刻み方によらず同じ時間で同じだけ近づく
+pub fn damp_factor(smoothing: f64, dt: f64) -> f64 {
+    1.0 - (-smoothing * dt).exp()
+}
+
+pub trait Damp {
+    fn damp(self, target: Self, smoothing: f64, dt: f64) -> Self;
+}
+
+impl Damp for f64 {
+    fn damp(self, target: Self, smoothing: f64, dt: f64) -> Self {
+        self + (target - self) * damp_factor(smoothing, dt)
+    }
+}
+
+impl Damp for V3 {
+    fn damp(self, target: Self, smoothing: f64, dt: f64) -> Self {
+        self + (target - self) * damp_factor(smoothing, dt)
+    }
+}
+
+impl Damp for Quaternion {
+    fn damp(self, target: Self, smoothing: f64, dt: f64) -> Self {
+        self.slerp(target, damp_factor(smoothing, dt))
+    }
+}
+
+pub fn damp<T: Damp>(current: T, target: T, smoothing: f64, dt: f64) -> T {
+    current.damp(target, smoothing, dt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v3;
+
+    #[test]
+    fn frame_rate_independent() {
+        let run = |fps: usize| {
+            let dt = 1.0 / fps as f64;
+            (0..fps).fold(0.0, |x, _| damp(x, 10.0, 5.0, dt))
+        };
+
+        assert!((run(30) - run(240)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn damp_v3() {
+        let a = v3!(0.0, 0.0, 0.0);
+        let b = v3!(2.0, 4.0, 6.0);
+        assert_eq!(damp(a, b, 1.0, 0.0), a);
+        assert!((damp(a, b, 1.0, 1e3) - b).mag() < 1e-9);
+    }
+}
@@ -0,0 +1,398 @@
+use std::f64::consts::SQRT_2;
+
+use crate::{
+    codec::{take, DecodeError},
+    quaternion::Quaternion,
+    se3::Se3,
+    v3,
+    vector::V3,
+};
+
+// ネットワーク向けに剛体変換の列を量子化して差分で送る形式
+// [バージョン: u8][フレーム数: varint][フレーム...]
+// フレームは [最大成分の番号: u8][回転3成分の差分: varint x3][位置の差分: varint x3]
+// 差分は直前のフレームの量子化値との差をジグザグ符号化したもの。設定は送受信側で共有する
+pub const DELTA_FORMAT_VERSION: u8 = 1;
+
+// 可変長整数(LEB128)。7ビットずつ下位から書き、続きがあれば最上位ビットを立てる
+pub fn write_varint(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+pub fn read_varint(input: &mut &[u8]) -> Result<u64, DecodeError> {
+    let mut result = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = take(input, 1)?[0];
+        let bits = (byte & 0x7f) as u64;
+        if shift == 63 && bits > 1 {
+            return Err(DecodeError::InvalidVarint);
+        }
+
+        result |= bits << shift;
+        if byte & 0x80 == 0 {
+            return Ok(result);
+        }
+    }
+
+    Err(DecodeError::InvalidVarint)
+}
+
+// 絶対値の小さい負数も短くなるよう、符号を最下位ビットに移す
+fn zigzag(n: i64) -> u64 {
+    ((n << 1) ^ (n >> 63)) as u64
+}
+
+fn unzigzag(n: u64) -> i64 {
+    ((n >> 1) as i64) ^ -((n & 1) as i64)
+}
+
+// 量子化の設定。位置はboundsの範囲をposition_bitsで、回転の3成分はrotation_bitsで量子化する
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeltaConfig {
+    pub min: V3,
+    pub max: V3,
+    pub position_bits: u32,
+    pub rotation_bits: u32,
+}
+
+// 量子化した1フレーム
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct QuantizedFrame {
+    largest: u8,
+    rotation: [i64; 3],
+    position: [i64; 3],
+}
+
+impl DeltaConfig {
+    pub fn new(min: V3, max: V3) -> Self {
+        Self {
+            min,
+            max,
+            position_bits: 16,
+            rotation_bits: 12,
+        }
+    }
+
+    fn levels(bits: u32) -> f64 {
+        ((1u64 << bits) - 1) as f64
+    }
+
+    // 位置の軸ごとの量子化幅
+    pub fn position_step(&self) -> V3 {
+        (self.max - self.min) / Self::levels(self.position_bits)
+    }
+
+    // 回転の成分の量子化幅。成分は [-1/√2, 1/√2] に収まる
+    pub fn rotation_step(&self) -> f64 {
+        SQRT_2 / Self::levels(self.rotation_bits)
+    }
+
+    // boundsの内側の位置なら、誤差はこの長さ以下
+    pub fn position_error_budget(&self) -> f64 {
+        self.position_step().mag() * 0.5
+    }
+
+    // 回転の誤差(ラジアン)の上限
+    // 3成分の誤差はそれぞれ量子化幅の半分h以下、復元する最大成分の誤差は3h以下なので
+    // 四元数の差は √12 h 以下で、回転角はその2倍で抑えられる
+    pub fn rotation_error_budget(&self) -> f64 {
+        2.0 * 12f64.sqrt() * self.rotation_step() * 0.5
+    }
+
+    fn quantize(&self, frame: &Se3) -> QuantizedFrame {
+        let levels = Self::levels(self.position_bits);
+        let p = frame.translation;
+        let axis = |v: f64, lo: f64, hi: f64| {
+            if hi <= lo {
+                return 0;
+            }
+            (((v - lo) / (hi - lo)).clamp(0.0, 1.0) * levels).round() as i64
+        };
+        let position = [
+            axis(p.x, self.min.x, self.max.x),
+            axis(p.y, self.min.y, self.max.y),
+            axis(p.z, self.min.z, self.max.z),
+        ];
+
+        // 絶対値が最大の成分を省き、それが正になるよう符号を揃える
+        let q = frame.rotation.normalize_or_identity();
+        let c = [q.w, q.x, q.y, q.z];
+        let largest = (0..4)
+            .max_by(|a, b| c[*a].abs().total_cmp(&c[*b].abs()))
+            .unwrap_or(0);
+        let sign = if c[largest] < 0.0 { -1.0 } else { 1.0 };
+
+        let levels = Self::levels(self.rotation_bits);
+        let mut rotation = [0; 3];
+        for (r, i) in rotation.iter_mut().zip((0..4).filter(|i| *i != largest)) {
+            let v = (c[i] * sign * SQRT_2 + 1.0) * 0.5;
+            *r = (v.clamp(0.0, 1.0) * levels).round() as i64;
+        }
+
+        QuantizedFrame {
+            largest: largest as u8,
+            rotation,
+            position,
+        }
+    }
+
+    fn dequantize(&self, frame: &QuantizedFrame) -> Se3 {
+        let levels = Self::levels(self.position_bits);
+        let axis = |q: i64, lo: f64, hi: f64| lo + (hi - lo) * (q as f64 / levels);
+        let position = v3!(
+            axis(frame.position[0], self.min.x, self.max.x),
+            axis(frame.position[1], self.min.y, self.max.y),
+            axis(frame.position[2], self.min.z, self.max.z),
+        );
+
+        let levels = Self::levels(self.rotation_bits);
+        let small = frame
+            .rotation
+            .map(|r| (r as f64 / levels * 2.0 - 1.0) / SQRT_2);
+        let sum = small.iter().map(|c| c * c).sum::<f64>();
+
+        let largest = frame.largest as usize;
+        let mut c = [0.0; 4];
+        c[largest] = (1.0 - sum).max(0.0).sqrt();
+        for (v, i) in small.iter().zip((0..4).filter(|i| *i != largest)) {
+            c[i] = *v;
+        }
+
+        let rotation = Quaternion {
+            w: c[0],
+            x: c[1],
+            y: c[2],
+            z: c[3],
+        }
+        .normalize_or_identity();
+
+        Se3::new(rotation, position)
+    }
+}
+
+// 量子化による誤差の実測値と、設定から決まる上限
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ErrorReport {
+    pub max_position_error: f64,
+    pub max_rotation_error: f64,
+    pub position_budget: f64,
+    pub rotation_budget: f64,
+}
+
+impl ErrorReport {
+    // boundsの外の位置があると上限を超える
+    pub fn within_budget(&self) -> bool {
+        self.max_position_error <= self.position_budget
+            && self.max_rotation_error <= self.rotation_budget
+    }
+}
+
+// 2つの回転の間の角度。qと-qは同じ回転として扱う
+fn rotation_error(a: &Quaternion, b: &Quaternion) -> f64 {
+    let d = (a.w * b.w + a.x * b.x + a.y * b.y + a.z * b.z).abs();
+    2.0 * d.min(1.0).acos()
+}
+
+pub fn encode_deltas(config: &DeltaConfig, frames: &[Se3]) -> (Vec<u8>, ErrorReport) {
+    let mut out = vec![DELTA_FORMAT_VERSION];
+    write_varint(frames.len() as u64, &mut out);
+
+    let mut report = ErrorReport {
+        max_position_error: 0.0,
+        max_rotation_error: 0.0,
+        position_budget: config.position_error_budget(),
+        rotation_budget: config.rotation_error_budget(),
+    };
+
+    let mut prev = QuantizedFrame {
+        largest: 0,
+        rotation: [0; 3],
+        position: [0; 3],
+    };
+    for frame in frames {
+        let q = config.quantize(frame);
+
+        out.push(q.largest);
+        for (a, b) in q.rotation.iter().zip(prev.rotation) {
+            write_varint(zigzag(a - b), &mut out);
+        }
+        for (a, b) in q.position.iter().zip(prev.position) {
+            write_varint(zigzag(a - b), &mut out);
+        }
+
+        let decoded = config.dequantize(&q);
+        report.max_position_error = report
+            .max_position_error
+            .max(decoded.translation.distance(&frame.translation));
+        report.max_rotation_error = report
+            .max_rotation_error
+            .max(rotation_error(&decoded.rotation, &frame.rotation));
+
+        prev = q;
+    }
+
+    (out, report)
+}
+
+pub fn decode_deltas(config: &DeltaConfig, bytes: &[u8]) -> Result<Vec<Se3>, DecodeError> {
+    let mut input = bytes;
+
+    let version = take(&mut input, 1)?[0];
+    if version != DELTA_FORMAT_VERSION {
+        return Err(DecodeError::UnsupportedVersion(version));
+    }
+
+    let count = read_varint(&mut input)?;
+    let mut frames = Vec::new();
+    let mut prev = QuantizedFrame {
+        largest: 0,
+        rotation: [0; 3],
+        position: [0; 3],
+    };
+    for _ in 0..count {
+        let largest = take(&mut input, 1)?[0];
+        if largest > 3 {
+            return Err(DecodeError::InvalidComponentIndex(largest));
+        }
+
+        let mut q = QuantizedFrame { largest, ..prev };
+        for r in q.rotation.iter_mut() {
+            *r = r.wrapping_add(unzigzag(read_varint(&mut input)?));
+        }
+        for p in q.position.iter_mut() {
+            *p = p.wrapping_add(unzigzag(read_varint(&mut input)?));
+        }
+
+        frames.push(config.dequantize(&q));
+        prev = q;
+    }
+
+    if !input.is_empty() {
+        return Err(DecodeError::TrailingBytes(input.len()));
+    }
+
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames() -> Vec<Se3> {
+        (0..50)
+            .map(|i| {
+                let t = i as f64 * 0.05;
+                let mut q = Quaternion::identitiy();
+                q.rotate_axis(v3!(0.0, 0.6, 0.8), t * 2.5);
+                Se3::new(q, v3!(t.sin() * 10.0, 1.0 + t, -t * 3.0))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn varint_roundtrip() {
+        for n in [0, 1, 127, 128, 300, u64::MAX] {
+            let mut out = Vec::new();
+            write_varint(n, &mut out);
+            assert_eq!(read_varint(&mut out.as_slice()), Ok(n));
+        }
+        for n in [0, -1, 1, i64::MIN, i64::MAX] {
+            assert_eq!(unzigzag(zigzag(n)), n);
+        }
+        assert_eq!(zigzag(-1), 1);
+
+        let overlong = [0xff; 11];
+        assert_eq!(
+            read_varint(&mut overlong.as_slice()),
+            Err(DecodeError::InvalidVarint)
+        );
+    }
+
+    #[test]
+    fn roundtrip_within_budget() {
+        let config = DeltaConfig::new(v3!(-20.0, -20.0, -20.0), v3!(20.0, 20.0, 20.0));
+        let frames = frames();
+        let (bytes, report) = encode_deltas(&config, &frames);
+        assert!(report.within_budget(), "{:?}", report);
+
+        // 滑らかな動きなら差分は小さく、生のf64の1/4以下になる
+        assert!(bytes.len() * 4 < frames.len() * 7 * 8, "{}", bytes.len());
+
+        let decoded = decode_deltas(&config, &bytes).unwrap();
+        assert_eq!(decoded.len(), frames.len());
+        for (a, b) in frames.iter().zip(&decoded) {
+            assert!(a.translation.distance(&b.translation) <= report.max_position_error + 1e-12);
+            assert!(rotation_error(&a.rotation, &b.rotation) <= report.max_rotation_error + 1e-12);
+        }
+    }
+
+    #[test]
+    fn smallest_three_keeps_sign_and_large_components() {
+        let config = DeltaConfig::new(v3!(0.0, 0.0, 0.0), v3!(1.0, 1.0, 1.0));
+        // 最大成分が負のものとw以外のもの
+        for q in [
+            Quaternion {
+                w: -0.9,
+                x: 0.3,
+                y: 0.1,
+                z: -0.3,
+            },
+            Quaternion {
+                w: 0.1,
+                x: -0.2,
+                y: 0.1,
+                z: 0.97,
+            },
+        ] {
+            let frame = Se3::new(q.normalize_or_identity(), v3!(0.5, 0.5, 0.5));
+            let decoded = config.dequantize(&config.quantize(&frame));
+            assert!(
+                rotation_error(&frame.rotation, &decoded.rotation)
+                    <= config.rotation_error_budget()
+            );
+        }
+    }
+
+    #[test]
+    fn out_of_bounds_exceeds_budget() {
+        let config = DeltaConfig::new(v3!(-1.0, -1.0, -1.0), v3!(1.0, 1.0, 1.0));
+        let (bytes, report) = encode_deltas(
+            &config,
+            &[Se3::new(Quaternion::identitiy(), v3!(3.0, 0.0, 0.0))],
+        );
+        assert!(!report.within_budget());
+        assert!((report.max_position_error - 2.0).abs() < 1e-3);
+
+        let decoded = decode_deltas(&config, &bytes).unwrap();
+        assert!((decoded[0].translation.x - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn errors() {
+        let config = DeltaConfig::new(v3!(0.0, 0.0, 0.0), v3!(1.0, 1.0, 1.0));
+        let (bytes, _) = encode_deltas(&config, &frames()[..2]);
+
+        assert_eq!(
+            decode_deltas(&config, &bytes[..bytes.len() - 1]).err(),
+            Some(DecodeError::UnexpectedEnd)
+        );
+
+        let mut bad = bytes.clone();
+        bad[2] = 7;
+        assert_eq!(
+            decode_deltas(&config, &bad).err(),
+            Some(DecodeError::InvalidComponentIndex(7))
+        );
+
+        let mut longer = bytes;
+        longer.push(0);
+        assert_eq!(
+            decode_deltas(&config, &longer).err(),
+            Some(DecodeError::TrailingBytes(1))
+        );
+    }
+}
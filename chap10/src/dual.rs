@@ -0,0 +1,269 @@
+use std::f64::consts::PI;
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use crate::utils::GameMath;
+
+// 二重数 a + bε (ε^2 = 0)。計算と同時に微分値が求まる自動微分
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dual {
+    pub value: f64,
+    pub derivative: f64,
+}
+
+impl Dual {
+    pub fn new(value: f64, derivative: f64) -> Self {
+        Dual { value, derivative }
+    }
+
+    // 定数。微分は0
+    pub fn constant(value: f64) -> Self {
+        Dual::new(value, 0.0)
+    }
+
+    // 微分する変数。d/dx x = 1
+    pub fn variable(value: f64) -> Self {
+        Dual::new(value, 1.0)
+    }
+
+    // 連鎖律: f(a + bε) = f(a) + f'(a)bε
+    fn chain(&self, value: f64, derivative: f64) -> Self {
+        Dual::new(value, derivative * self.derivative)
+    }
+
+    pub fn sin(self) -> Self {
+        let (s, c) = self.value.sin_cos();
+        self.chain(s, c)
+    }
+
+    pub fn cos(self) -> Self {
+        let (s, c) = self.value.sin_cos();
+        self.chain(c, -s)
+    }
+
+    pub fn tan(self) -> Self {
+        let t = self.value.tan();
+        self.chain(t, 1.0 + t * t)
+    }
+
+    pub fn sqrt(self) -> Self {
+        let r = self.value.sqrt();
+        self.chain(r, 0.5 / r)
+    }
+
+    pub fn exp(self) -> Self {
+        let e = self.value.exp();
+        self.chain(e, e)
+    }
+
+    pub fn ln(self) -> Self {
+        self.chain(self.value.ln(), 1.0 / self.value)
+    }
+
+    pub fn powf(self, n: f64) -> Self {
+        self.chain(self.value.powf(n), n * self.value.powf(n - 1.0))
+    }
+
+    pub fn abs(self) -> Self {
+        self.chain(self.value.abs(), self.value.signum())
+    }
+
+    pub fn acos(self) -> Self {
+        self.chain(
+            self.value.acos(),
+            -1.0 / (1.0 - self.value * self.value).sqrt(),
+        )
+    }
+
+    pub fn asin(self) -> Self {
+        self.chain(
+            self.value.asin(),
+            1.0 / (1.0 - self.value * self.value).sqrt(),
+        )
+    }
+
+    pub fn atan2(self, x: Dual) -> Self {
+        let y = self;
+        let denom = x.value * x.value + y.value * y.value;
+
+        Dual::new(
+            y.value.atan2(x.value),
+            (x.value * y.derivative - y.value * x.derivative) / denom,
+        )
+    }
+
+    // 区分的に一定なので微分は0
+    pub fn floor(self) -> Self {
+        Dual::new(self.value.floor(), 0.0)
+    }
+}
+
+// f(x)の値とx = atでの微分値
+pub fn derivative<F>(f: F, at: f64) -> (f64, f64)
+where
+    F: Fn(Dual) -> Dual,
+{
+    let result = f(Dual::variable(at));
+    (result.value, result.derivative)
+}
+
+impl GameMath for Dual {
+    // ラップは定数の加算なので微分は変わらない
+    fn wrap_pi(self) -> Self {
+        Dual::new(self.value.wrap_pi(), self.derivative)
+    }
+
+    // クランプされた範囲では定数になる
+    fn safe_acos(self) -> Self {
+        if self.value <= -1.0 {
+            return Dual::constant(PI);
+        }
+        if self.value >= 1.0 {
+            return Dual::constant(0.0);
+        }
+
+        self.acos()
+    }
+}
+
+impl From<f64> for Dual {
+    fn from(value: f64) -> Self {
+        Dual::constant(value)
+    }
+}
+
+impl Add for Dual {
+    type Output = Dual;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Dual::new(self.value + rhs.value, self.derivative + rhs.derivative)
+    }
+}
+
+impl AddAssign for Dual {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Dual {
+    type Output = Dual;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Dual::new(self.value - rhs.value, self.derivative - rhs.derivative)
+    }
+}
+
+impl SubAssign for Dual {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul for Dual {
+    type Output = Dual;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Dual::new(
+            self.value * rhs.value,
+            self.derivative * rhs.value + self.value * rhs.derivative,
+        )
+    }
+}
+
+impl MulAssign for Dual {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+impl Div for Dual {
+    type Output = Dual;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        Dual::new(
+            self.value / rhs.value,
+            (self.derivative * rhs.value - self.value * rhs.derivative) / (rhs.value * rhs.value),
+        )
+    }
+}
+
+impl Neg for Dual {
+    type Output = Dual;
+
+    fn neg(self) -> Self::Output {
+        Dual::new(-self.value, -self.derivative)
+    }
+}
+
+impl Add<f64> for Dual {
+    type Output = Dual;
+
+    fn add(self, rhs: f64) -> Self::Output {
+        self + Dual::constant(rhs)
+    }
+}
+
+impl Sub<f64> for Dual {
+    type Output = Dual;
+
+    fn sub(self, rhs: f64) -> Self::Output {
+        self - Dual::constant(rhs)
+    }
+}
+
+impl Mul<f64> for Dual {
+    type Output = Dual;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Dual::new(self.value * rhs, self.derivative * rhs)
+    }
+}
+
+impl Div<f64> for Dual {
+    type Output = Dual;
+
+    fn div(self, rhs: f64) -> Self::Output {
+        Dual::new(self.value / rhs, self.derivative / rhs)
+    }
+}
+
+impl Mul<Dual> for f64 {
+    type Output = Dual;
+
+    fn mul(self, rhs: Dual) -> Self::Output {
+        rhs * self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polynomial() {
+        // d/dx (3x^2 + 2x) = 6x + 2
+        let (value, d) = derivative(|x| 3.0 * x * x + x * 2.0, 2.0);
+        assert_eq!(value, 16.0);
+        assert_eq!(d, 14.0);
+    }
+
+    #[test]
+    fn chain_rule() {
+        // d/dx sin(x^2) = 2x cos(x^2)
+        let (_, d) = derivative(|x| (x * x).sin(), 0.5);
+        assert!((d - 2.0 * 0.5 * 0.25f64.cos()).abs() < 1e-12);
+
+        // d/dx x / (1 + x) = 1 / (1 + x)^2
+        let (_, d) = derivative(|x| x / (x + 1.0), 1.0);
+        assert!((d - 0.25).abs() < 1e-12);
+    }
+
+    #[test]
+    fn game_math() {
+        let (_, d) = derivative(|x| (x * 4.0).wrap_pi(), 1.0);
+        assert_eq!(d, 4.0);
+
+        let (value, d) = derivative(|x| x.safe_acos(), 2.0);
+        assert_eq!((value, d), (0.0, 0.0));
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{matrix::Matrix3x4, quaternion::Quaternion, v3, vector::V3};
+
+// 単位球をアフィン変換した楕円体。非一様スケールのかかった球はこれになる
+pub struct Ellipsoid {
+    // 単位球のローカル座標 -> ワールド座標
+    transform: Matrix3x4,
+    inverse: Matrix3x4,
+}
+
+impl Ellipsoid {
+    // 変換が潰れていて逆行列がないときはNone
+    pub fn new(transform: Matrix3x4) -> Option<Self> {
+        let inverse = transform.try_inverse()?;
+
+        Some(Self { transform, inverse })
+    }
+
+    pub fn from_sphere(center: V3, radius: f64) -> Option<Self> {
+        Self::new(
+            Matrix3x4::builder()
+                .scale(v3!(radius, radius, radius))
+                .translate(center)
+                .build(),
+        )
+    }
+
+    // 各軸の半径radiiでスケールし、orientationで回転してからcenterへ移動する
+    pub fn from_radii(center: V3, radii: V3, orientation: Quaternion) -> Option<Self> {
+        Self::new(
+            Matrix3x4::builder()
+                .scale(radii)
+                .rotate_quaternion(orientation)
+                .translate(center)
+                .build(),
+        )
+    }
+
+    pub fn center(&self) -> V3 {
+        v3!(self.transform.tx, self.transform.ty, self.transform.tz)
+    }
+
+    // 正確なAABB。単位球上の点 u * M の各軸の最大値は、Mの列ベクトルの長さになる
+    pub fn aabb(&self) -> (V3, V3) {
+        let m = &self.transform;
+        let extent = v3!(
+            (m.m11 * m.m11 + m.m21 * m.m21 + m.m31 * m.m31).sqrt(),
+            (m.m12 * m.m12 + m.m22 * m.m22 + m.m32 * m.m32).sqrt(),
+            (m.m13 * m.m13 + m.m23 * m.m23 + m.m33 * m.m33).sqrt(),
+        );
+        let center = self.center();
+
+        (center - extent, center + extent)
+    }
+
+    pub fn contains(&self, p: V3) -> bool {
+        (p * self.inverse).mag_squared() <= 1.0
+    }
+
+    // 半直線 origin + t * direction (t >= 0) と最初に交わるt
+    // ローカル空間の単位球と交差させる。アフィン変換ではtはそのまま使える
+    pub fn intersect_ray(&self, origin: V3, direction: V3) -> Option<f64> {
+        let o = origin * self.inverse;
+        let d = (origin + direction) * self.inverse - o;
+
+        let a = d.mag_squared();
+        if a == 0.0 {
+            return None;
+        }
+        let b = o.dot(&d);
+        let c = o.mag_squared() - 1.0;
+
+        let discriminant = b * b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        let root = discriminant.sqrt();
+        let t0 = (-b - root) / a;
+        let t1 = (-b + root) / a;
+
+        // 内側から撃ったときは出ていく方の交点
+        if t0 >= 0.0 {
+            Some(t0)
+        } else if t1 >= 0.0 {
+            Some(t1)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sphere() {
+        let e = Ellipsoid::from_sphere(v3!(1.0, 0.0, 0.0), 2.0).unwrap();
+        assert_eq!(e.aabb(), (v3!(-1.0, -2.0, -2.0), v3!(3.0, 2.0, 2.0)));
+        assert!(e.contains(v3!(2.5, 0.0, 0.0)));
+        assert!(!e.contains(v3!(3.5, 0.0, 0.0)));
+
+        let t = e
+            .intersect_ray(v3!(10.0, 0.0, 0.0), v3!(-1.0, 0.0, 0.0))
+            .unwrap();
+        assert!((t - 7.0).abs() < 1e-12);
+        assert_eq!(
+            e.intersect_ray(v3!(10.0, 5.0, 0.0), v3!(-1.0, 0.0, 0.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn tiny_and_degenerate() {
+        // 行列式は1.25e-13だが、スケールに対しては十分正則
+        let c = v3!(1.0, 2.0, 3.0);
+        let e = Ellipsoid::from_sphere(c, 5e-5).unwrap();
+        assert!(e.contains(c + v3!(4e-5, 0.0, 0.0)));
+        assert!(!e.contains(c + v3!(6e-5, 0.0, 0.0)));
+
+        let flat = Ellipsoid::from_radii(c, v3!(1.0, 2.0, 0.0), Quaternion::identitiy());
+        assert!(flat.is_none());
+    }
+
+    #[test]
+    fn rotated_ellipsoid() {
+        // z軸まわりに90度回すとx方向とy方向の半径が入れ替わる
+        let q = Quaternion {
+            w: 0.5f64.sqrt(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.5f64.sqrt(),
+        };
+        let e = Ellipsoid::from_radii(v3!(0.0, 0.0, 0.0), v3!(1.0, 2.0, 3.0), q).unwrap();
+        let (min, max) = e.aabb();
+        assert!((max - v3!(2.0, 1.0, 3.0)).mag() < 1e-12);
+        assert!((min + v3!(2.0, 1.0, 3.0)).mag() < 1e-12);
+
+        assert!(e.contains(v3!(1.9, 0.0, 0.0)));
+        assert!(!e.contains(v3!(0.0, 1.1, 0.0)));
+
+        let t = e
+            .intersect_ray(v3!(0.0, 0.0, 0.0), v3!(0.0, 0.0, 1.0))
+            .unwrap();
+        assert!((t - 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn degenerate() {
+        assert!(Ellipsoid::from_sphere(v3!(1.0, 2.0, 3.0), 0.0).is_none());
+    }
+}
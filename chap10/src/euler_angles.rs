@@ -1,6 +1,7 @@
 use std::f64::consts::PI;
 
 use crate::{
+    debug_assert_finite,
     matrix::{Matrix3x4, RotationMatrix},
     quaternion::Quaternion,
     utils::{GameMath, PI_OVER_2},
@@ -13,18 +14,28 @@ pub struct EulerAngles {
     pub bank: f64,
 }
 
-const EULER_ANGLES_IDENTITY: EulerAngles = EulerAngles {
+pub const EULER_ANGLES_IDENTITY: EulerAngles = EulerAngles {
     heading: 0.0,
     pitch: 0.0,
     bank: 0.0,
 };
 
 impl EulerAngles {
+    pub fn is_finite(&self) -> bool {
+        self.heading.is_finite() && self.pitch.is_finite() && self.bank.is_finite()
+    }
+
+    pub fn is_nan(&self) -> bool {
+        self.heading.is_nan() || self.pitch.is_nan() || self.bank.is_nan()
+    }
+
     pub fn from_obj_to_inertial_quaternion(q: Quaternion) -> Self {
+        debug_assert_finite!(q);
+
         let mut result = EulerAngles::identity();
 
         // 回転行列の対応する要素から、四元数->オイラー角の変換を行う
-        let sp = -2.0 * (q.x * q.z - q.w * q.x);
+        let sp = -2.0 * (q.y * q.z - q.w * q.x);
 
         // ジンバルロック(誤差込み)
         if sp.abs() > 0.9999 {
@@ -42,7 +53,9 @@ impl EulerAngles {
         result
     }
 
-    pub fn from_inertial_to_obj_quaternion(q: Quaternion) {
+    pub fn from_inertial_to_obj_quaternion(q: Quaternion) -> EulerAngles {
+        debug_assert_finite!(q);
+
         let mut result = EulerAngles::identity();
 
         let sp: f64 = -2.0 * (q.y * q.z + q.w * q.x);
@@ -59,28 +72,32 @@ impl EulerAngles {
             result.heading = (q.x * q.z - q.w * q.y).atan2(0.5 - q.x * q.x - q.y * q.y);
             result.bank = (q.x * q.y - q.w * q.z).atan2(0.5 - q.x * q.x - q.z * q.z);
         }
+
+        result
     }
 
-    pub fn from_obj_to_world_matrix(m: Matrix3x4) {
+    pub fn from_obj_to_world_matrix(m: Matrix3x4) -> EulerAngles {
         let mut result = EulerAngles::identity();
         let sp = -m.m32;
 
-        if sp.abs() > 9.99999 {
+        if sp.abs() > 0.9999 {
             result.pitch = PI_OVER_2 * sp;
-            result.heading = (-m.m23).atan2(m.m11);
+            result.heading = (-m.m13).atan2(m.m11);
             result.bank = 0.0;
         } else {
             result.heading = m.m31.atan2(m.m33);
             result.pitch = sp.asin();
             result.bank = m.m12.atan2(m.m22);
         }
+
+        result
     }
 
     pub fn from_world_to_obj_matrix(m: Matrix3x4) -> EulerAngles {
         let mut result = EulerAngles::identity();
         let sp = -m.m23;
 
-        if sp.abs() > 9.99999 {
+        if sp.abs() > 0.9999 {
             result.pitch = PI_OVER_2 * sp;
             result.heading = (-m.m31).atan2(m.m11);
             result.bank = 0.0;
@@ -97,7 +114,7 @@ impl EulerAngles {
         let mut result = EulerAngles::identity();
         let sp = -m.m23;
 
-        if sp.abs() > 9.99999 {
+        if sp.abs() > 0.9999 {
             result.pitch = PI_OVER_2 * sp;
             result.heading = (-m.m31).atan2(m.m11);
             result.bank = 0.0;
@@ -149,3 +166,86 @@ impl EulerAngles {
         self.heading = self.heading.wrap_pi();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{approx::ApproxEq, v3, vector::V3};
+
+    fn orientations() -> [EulerAngles; 4] {
+        [
+            EulerAngles {
+                heading: 0.3,
+                pitch: -0.4,
+                bank: 1.2,
+            },
+            EulerAngles {
+                heading: -2.5,
+                pitch: 1.1,
+                bank: -0.7,
+            },
+            // ジンバルロック。bankはheadingに吸収される
+            EulerAngles {
+                heading: 0.8,
+                pitch: PI_OVER_2,
+                bank: 0.0,
+            },
+            EulerAngles {
+                heading: -1.9,
+                pitch: -PI_OVER_2,
+                bank: 0.0,
+            },
+        ]
+    }
+
+    fn same_orientation(a: EulerAngles, b: EulerAngles) -> bool {
+        RotationMatrix::from_orientation(a).abs_diff_eq(&RotationMatrix::from_orientation(b), 1e-6)
+    }
+
+    #[test]
+    fn rotation_matrix_round_trip() {
+        for e in orientations() {
+            let m = RotationMatrix::from_orientation(e);
+            let back = EulerAngles::from_rotation_matrix(m);
+            assert!(back.abs_diff_eq(&e, 1e-6));
+        }
+    }
+
+    #[test]
+    fn transform_matrix_round_trip() {
+        let pos = v3!(1.0, -2.0, 3.0);
+
+        for e in orientations() {
+            let local_to_parent = Matrix3x4::from_local_to_parent_euler(pos, e);
+            assert!(same_orientation(
+                EulerAngles::from_obj_to_world_matrix(local_to_parent),
+                e
+            ));
+
+            let parent_to_local = Matrix3x4::from_parent_to_local_euler(pos, e);
+            assert!(same_orientation(
+                EulerAngles::from_world_to_obj_matrix(parent_to_local),
+                e
+            ));
+        }
+    }
+
+    #[test]
+    fn quaternion_round_trip() {
+        for e in orientations() {
+            let mut q = Quaternion::identitiy();
+            q.rotate_obj_to_inertial(e);
+            assert!(same_orientation(
+                EulerAngles::from_obj_to_inertial_quaternion(q),
+                e
+            ));
+
+            let mut q = Quaternion::identitiy();
+            q.rotate_inertial_to_obj(e);
+            assert!(same_orientation(
+                EulerAngles::from_inertial_to_obj_quaternion(q),
+                e
+            ));
+        }
+    }
+}
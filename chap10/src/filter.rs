@@ -0,0 +1,210 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CubicFilter {
+    // 補間型。サンプル点を通る
+    CatmullRom,
+    // 近似型。サンプル点を通らないが滑らか
+    BSpline,
+}
+
+impl CubicFilter {
+    // サンプル p[-1], p[0], p[1], p[2] に対する重み。tは p[0] からの距離 [0, 1]
+    pub fn weights(&self, t: f64) -> [f64; 4] {
+        let t2 = t * t;
+        let t3 = t2 * t;
+
+        match self {
+            CubicFilter::CatmullRom => [
+                0.5 * (-t3 + 2.0 * t2 - t),
+                0.5 * (3.0 * t3 - 5.0 * t2 + 2.0),
+                0.5 * (-3.0 * t3 + 4.0 * t2 + t),
+                0.5 * (t3 - t2),
+            ],
+            CubicFilter::BSpline => {
+                let s = 1.0 - t;
+                [
+                    s * s * s / 6.0,
+                    (3.0 * t3 - 6.0 * t2 + 4.0) / 6.0,
+                    (-3.0 * t3 + 3.0 * t2 + 3.0 * t + 1.0) / 6.0,
+                    t3 / 6.0,
+                ]
+            }
+        }
+    }
+
+    // 4x4近傍に対する重み。[y][x]の順
+    pub fn weights_2d(&self, tx: f64, ty: f64) -> [[f64; 4]; 4] {
+        let wx = self.weights(tx);
+        let wy = self.weights(ty);
+
+        let mut result = [[0.0; 4]; 4];
+        for (row, y) in result.iter_mut().zip(wy) {
+            for (w, x) in row.iter_mut().zip(wx) {
+                *w = x * y;
+            }
+        }
+
+        result
+    }
+
+    // 整数座標のサンプルを返す関数から、任意の位置の値を求める
+    pub fn sample_1d<F>(&self, sample: F, x: f64) -> f64
+    where
+        F: Fn(i64) -> f64,
+    {
+        let base = x.floor();
+        let i = base as i64;
+
+        self.weights(x - base)
+            .iter()
+            .enumerate()
+            .map(|(k, w)| w * sample(i + k as i64 - 1))
+            .sum()
+    }
+
+    // グリッドの範囲外の扱いはsample側で決める(クランプなど)
+    pub fn sample_2d<F>(&self, sample: F, x: f64, y: f64) -> f64
+    where
+        F: Fn(i64, i64) -> f64,
+    {
+        let base_x = x.floor();
+        let base_y = y.floor();
+        let i = base_x as i64;
+        let j = base_y as i64;
+
+        let mut result = 0.0;
+        for (dy, row) in self.weights_2d(x - base_x, y - base_y).iter().enumerate() {
+            for (dx, w) in row.iter().enumerate() {
+                result += w * sample(i + dx as i64 - 1, j + dy as i64 - 1);
+            }
+        }
+
+        result
+    }
+}
+
+// 積分画像。任意の矩形領域の合計を定数時間で求める
+pub struct SummedAreaTable {
+    width: usize,
+    height: usize,
+    // 先頭に0の行と列を足した (width + 1) * (height + 1)
+    sums: Vec<f64>,
+}
+
+impl SummedAreaTable {
+    pub fn new<F>(width: usize, height: usize, sample: F) -> Self
+    where
+        F: Fn(usize, usize) -> f64,
+    {
+        let stride = width + 1;
+        let mut sums = vec![0.0; stride * (height + 1)];
+
+        for y in 0..height {
+            let mut row_sum = 0.0;
+            for x in 0..width {
+                row_sum += sample(x, y);
+                sums[(y + 1) * stride + x + 1] = sums[y * stride + x + 1] + row_sum;
+            }
+        }
+
+        Self {
+            width,
+            height,
+            sums,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // [x0, x1) x [y0, y1) の合計。範囲はグリッドにクランプする
+    pub fn sum(&self, x0: usize, y0: usize, x1: usize, y1: usize) -> f64 {
+        let x0 = x0.min(self.width);
+        let x1 = x1.min(self.width);
+        let y0 = y0.min(self.height);
+        let y1 = y1.min(self.height);
+        if x0 >= x1 || y0 >= y1 {
+            return 0.0;
+        }
+
+        let stride = self.width + 1;
+        self.sums[y1 * stride + x1] - self.sums[y0 * stride + x1] - self.sums[y1 * stride + x0]
+            + self.sums[y0 * stride + x0]
+    }
+
+    pub fn average(&self, x0: usize, y0: usize, x1: usize, y1: usize) -> f64 {
+        let x1 = x1.min(self.width);
+        let y1 = y1.min(self.height);
+        if x0 >= x1 || y0 >= y1 {
+            return 0.0;
+        }
+
+        self.sum(x0, y0, x1, y1) / ((x1 - x0) * (y1 - y0)) as f64
+    }
+
+    // (x, y) を中心とした一辺 2 * radius + 1 の箱フィルタ。端では範囲内だけで平均する
+    pub fn box_filter(&self, x: usize, y: usize, radius: usize) -> f64 {
+        self.average(
+            x.saturating_sub(radius),
+            y.saturating_sub(radius),
+            x + radius + 1,
+            y + radius + 1,
+        )
+    }
+
+    // グリッド全体に箱フィルタをかけた結果を行優先で返す
+    pub fn blur(&self, radius: usize) -> Vec<f64> {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .map(|(x, y)| self.box_filter(x, y, radius))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weights_sum_to_one() {
+        for filter in [CubicFilter::CatmullRom, CubicFilter::BSpline] {
+            for t in [0.0, 0.25, 0.5, 0.75, 1.0] {
+                let sum: f64 = filter.weights(t).iter().sum();
+                assert!((sum - 1.0).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn catmull_rom_interpolates() {
+        let grid = |x: i64, y: i64| (x * 10 + y) as f64;
+        assert_eq!(CubicFilter::CatmullRom.sample_2d(grid, 3.0, 4.0), 34.0);
+    }
+
+    #[test]
+    fn linear_data_is_preserved() {
+        let line = |x: i64| 2.0 * x as f64 + 1.0;
+        for filter in [CubicFilter::CatmullRom, CubicFilter::BSpline] {
+            assert!((filter.sample_1d(line, 2.5) - 6.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn summed_area_sum() {
+        let table = SummedAreaTable::new(4, 3, |x, y| (x + y * 4) as f64);
+        assert_eq!(table.sum(0, 0, 4, 3), 66.0);
+        assert_eq!(table.sum(1, 1, 3, 3), 5.0 + 6.0 + 9.0 + 10.0);
+        assert_eq!(table.average(1, 1, 3, 3), 7.5);
+    }
+
+    #[test]
+    fn box_filter_clamps_at_edges() {
+        let table = SummedAreaTable::new(3, 3, |_, _| 2.0);
+        assert_eq!(table.box_filter(0, 0, 1), 2.0);
+        assert_eq!(table.blur(5), vec![2.0; 9]);
+    }
+}
@@ -0,0 +1,186 @@
+use crate::{matrix::Matrix3x4, v3, vector::V3};
+
+// WGS84楕円体
+pub const WGS84_A: f64 = 6_378_137.0;
+pub const WGS84_F: f64 = 1.0 / 298.257_223_563;
+pub const WGS84_B: f64 = WGS84_A * (1.0 - WGS84_F);
+pub const WGS84_E2: f64 = WGS84_F * (2.0 - WGS84_F);
+
+// 緯度・経度はラジアン、高度は楕円体面からのメートル
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Geodetic {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: f64,
+}
+
+impl Geodetic {
+    pub fn new(latitude: f64, longitude: f64, altitude: f64) -> Self {
+        Self {
+            latitude,
+            longitude,
+            altitude,
+        }
+    }
+
+    // 卯酉線曲率半径
+    fn prime_vertical_radius(latitude: f64) -> f64 {
+        let s = latitude.sin();
+        WGS84_A / (1.0 - WGS84_E2 * s * s).sqrt()
+    }
+
+    // 地球中心地球固定座標(x: 経度0の赤道, y: 東経90度の赤道, z: 北極)
+    pub fn to_ecef(&self) -> V3 {
+        let (sin_lat, cos_lat) = self.latitude.sin_cos();
+        let (sin_lon, cos_lon) = self.longitude.sin_cos();
+        let n = Self::prime_vertical_radius(self.latitude);
+
+        v3![
+            (n + self.altitude) * cos_lat * cos_lon,
+            (n + self.altitude) * cos_lat * sin_lon,
+            (n * (1.0 - WGS84_E2) + self.altitude) * sin_lat,
+        ]
+    }
+
+    // 緯度を反復で求める。極付近でも破綻しない高度の式を使う
+    pub fn from_ecef(p: V3) -> Self {
+        let r = (p.x * p.x + p.y * p.y).sqrt();
+        let longitude = p.y.atan2(p.x);
+        let mut latitude = p.z.atan2(r * (1.0 - WGS84_E2));
+
+        for _ in 0..16 {
+            let n = Self::prime_vertical_radius(latitude);
+            let next = (p.z + WGS84_E2 * n * latitude.sin()).atan2(r);
+            let converged = (next - latitude).abs() < 1e-15;
+            latitude = next;
+            if converged {
+                break;
+            }
+        }
+
+        let (sin_lat, cos_lat) = latitude.sin_cos();
+        let altitude =
+            r * cos_lat + p.z * sin_lat - WGS84_A * (1.0 - WGS84_E2 * sin_lat * sin_lat).sqrt();
+
+        Self {
+            latitude,
+            longitude,
+            altitude,
+        }
+    }
+
+    // ECEFで表した東・北・上の単位ベクトル
+    fn enu_axes(&self) -> (V3, V3, V3) {
+        let (sin_lat, cos_lat) = self.latitude.sin_cos();
+        let (sin_lon, cos_lon) = self.longitude.sin_cos();
+
+        (
+            v3![-sin_lon, cos_lon, 0.0],
+            v3![-sin_lat * cos_lon, -sin_lat * sin_lon, cos_lat],
+            v3![cos_lat * cos_lon, cos_lat * sin_lon, sin_lat],
+        )
+    }
+}
+
+// 各行が局所座標の軸、平行移動が原点になる(局所 -> ECEF)
+fn local_to_ecef(x: V3, y: V3, z: V3, origin: V3) -> Matrix3x4 {
+    Matrix3x4 {
+        m11: x.x,
+        m12: x.y,
+        m13: x.z,
+        m21: y.x,
+        m22: y.y,
+        m23: y.z,
+        m31: z.x,
+        m32: z.y,
+        m33: z.z,
+        tx: origin.x,
+        ty: origin.y,
+        tz: origin.z,
+    }
+}
+
+// local_to_ecefの逆。回転部分は転置
+fn ecef_to_local(x: V3, y: V3, z: V3, origin: V3) -> Matrix3x4 {
+    Matrix3x4 {
+        m11: x.x,
+        m12: y.x,
+        m13: z.x,
+        m21: x.y,
+        m22: y.y,
+        m23: z.y,
+        m31: x.z,
+        m32: y.z,
+        m33: z.z,
+        tx: -(origin.x * x.x + origin.y * x.y + origin.z * x.z),
+        ty: -(origin.x * y.x + origin.y * y.y + origin.z * y.z),
+        tz: -(origin.x * z.x + origin.y * z.y + origin.z * z.z),
+    }
+}
+
+impl Matrix3x4 {
+    // 東・北・上の局所接平面座標
+    pub fn from_enu_to_ecef(origin: Geodetic) -> Self {
+        let (e, n, u) = origin.enu_axes();
+        local_to_ecef(e, n, u, origin.to_ecef())
+    }
+
+    pub fn from_ecef_to_enu(origin: Geodetic) -> Self {
+        let (e, n, u) = origin.enu_axes();
+        ecef_to_local(e, n, u, origin.to_ecef())
+    }
+
+    // 北・東・下の局所接平面座標
+    pub fn from_ned_to_ecef(origin: Geodetic) -> Self {
+        let (e, n, u) = origin.enu_axes();
+        local_to_ecef(n, e, -u, origin.to_ecef())
+    }
+
+    pub fn from_ecef_to_ned(origin: Geodetic) -> Self {
+        let (e, n, u) = origin.enu_axes();
+        ecef_to_local(n, e, -u, origin.to_ecef())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ecef_round_trip() {
+        for (lat, lon, alt) in [
+            (0.0, 0.0, 0.0),
+            (0.6, 2.4, 1234.5),
+            (-1.2, -0.3, -50.0),
+            (1.5, 1.0, 10.0),
+        ] {
+            let g = Geodetic::new(lat, lon, alt);
+            let back = Geodetic::from_ecef(g.to_ecef());
+            assert!((back.latitude - lat).abs() < 1e-9);
+            assert!((back.longitude - lon).abs() < 1e-9);
+            assert!((back.altitude - alt).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn equator_prime_meridian() {
+        let p = Geodetic::new(0.0, 0.0, 0.0).to_ecef();
+        assert_eq!(p, v3![WGS84_A, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn local_frames() {
+        let origin = Geodetic::new(0.6, 2.4, 100.0);
+        let up = Geodetic::new(0.6, 2.4, 110.0).to_ecef();
+
+        let enu = up * Matrix3x4::from_ecef_to_enu(origin);
+        assert!((enu - v3![0.0, 0.0, 10.0]).mag() < 1e-6);
+
+        let ned = up * Matrix3x4::from_ecef_to_ned(origin);
+        assert!((ned - v3![0.0, 0.0, -10.0]).mag() < 1e-6);
+
+        let back = v3![3.0, -4.0, 5.0] * Matrix3x4::from_enu_to_ecef(origin);
+        let local = back * Matrix3x4::from_ecef_to_enu(origin);
+        assert!((local - v3![3.0, -4.0, 5.0]).mag() < 1e-6);
+    }
+}
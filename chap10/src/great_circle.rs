@@ -0,0 +1,152 @@
+use crate::{geodetic::Geodetic, v3, vector::V3};
+
+// 球面上の位置。ラジアン
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatLon {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl LatLon {
+    pub fn new(latitude: f64, longitude: f64) -> Self {
+        Self {
+            latitude,
+            longitude,
+        }
+    }
+
+    // geodetic::Geodetic と緯度・経度の定義は同じ。測地緯度をそのまま球面の緯度とみなす
+    pub fn to_geodetic(&self, altitude: f64) -> Geodetic {
+        Geodetic::new(self.latitude, self.longitude, altitude)
+    }
+
+    // 球の中心からの単位ベクトル。y軸が北極、経度0が+z、東経90度が+x
+    // 本書の座標系(y上、z前)に合わせているので、ECEF(z軸が北極、経度0が+x)とは
+    // 軸の割り当てが異なる。変換にはecef_to_direction / direction_to_ecefを使う
+    pub fn to_direction(&self) -> V3 {
+        let (sin_lat, cos_lat) = self.latitude.sin_cos();
+        let (sin_lon, cos_lon) = self.longitude.sin_cos();
+
+        v3!(cos_lat * sin_lon, sin_lat, cos_lat * cos_lon)
+    }
+
+    pub fn from_direction(d: V3) -> Self {
+        let d = d.normalize();
+
+        Self {
+            latitude: d.y.clamp(-1.0, 1.0).asin(),
+            longitude: d.x.atan2(d.z),
+        }
+    }
+}
+
+impl From<Geodetic> for LatLon {
+    fn from(g: Geodetic) -> Self {
+        Self::new(g.latitude, g.longitude)
+    }
+}
+
+// ECEFの方向をLatLon::to_directionと同じ軸(y: 北極, z: 経度0, x: 東経90度)に並べ替える
+// 軸の巡回置換なので長さも向きの関係も変わらない
+pub fn ecef_to_direction(v: V3) -> V3 {
+    v3!(v.y, v.z, v.x)
+}
+
+// ecef_to_directionの逆
+pub fn direction_to_ecef(d: V3) -> V3 {
+    v3!(d.z, d.x, d.y)
+}
+
+// 中心角。acosより近い点・対蹠点で精度が落ちにくい
+pub fn central_angle(a: V3, b: V3) -> f64 {
+    a.cross(&b).mag().atan2(a.dot(&b))
+}
+
+// 大円距離
+pub fn distance(a: LatLon, b: LatLon, radius: f64) -> f64 {
+    central_angle(a.to_direction(), b.to_direction()) * radius
+}
+
+// 大円に沿った補間
+pub fn interpolate(a: LatLon, b: LatLon, t: f64) -> LatLon {
+    LatLon::from_direction(a.to_direction().slerp(&b.to_direction(), t))
+}
+
+// aからbへ向かう初期方位。北を0とした時計回り [-PI, PI]
+pub fn initial_bearing(a: LatLon, b: LatLon) -> f64 {
+    let d_lon = b.longitude - a.longitude;
+    let (sin_lat1, cos_lat1) = a.latitude.sin_cos();
+    let (sin_lat2, cos_lat2) = b.latitude.sin_cos();
+
+    let y = d_lon.sin() * cos_lat2;
+    let x = cos_lat1 * sin_lat2 - sin_lat1 * cos_lat2 * d_lon.cos();
+
+    y.atan2(x)
+}
+
+// startから方位bearingに距離distanceだけ進んだ地点
+pub fn destination(start: LatLon, bearing: f64, distance: f64, radius: f64) -> LatLon {
+    let delta = distance / radius;
+    let (sin_delta, cos_delta) = delta.sin_cos();
+    let (sin_lat, cos_lat) = start.latitude.sin_cos();
+
+    let sin_lat2 = sin_lat * cos_delta + cos_lat * sin_delta * bearing.cos();
+    let latitude = sin_lat2.clamp(-1.0, 1.0).asin();
+    let longitude = start.longitude
+        + (bearing.sin() * sin_delta * cos_lat).atan2(cos_delta - sin_lat * sin_lat2);
+
+    LatLon {
+        latitude,
+        longitude,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::PI_OVER_2;
+
+    #[test]
+    fn quarter_circle() {
+        let a = LatLon::new(0.0, 0.0);
+        let b = LatLon::new(0.0, PI_OVER_2);
+        assert!((distance(a, b, 2.0) - PI_OVER_2 * 2.0).abs() < 1e-12);
+        assert!((initial_bearing(a, b) - PI_OVER_2).abs() < 1e-12);
+
+        let mid = interpolate(a, b, 0.5);
+        assert!(mid.latitude.abs() < 1e-12);
+        assert!((mid.longitude - PI_OVER_2 * 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn antipodal_interpolation() {
+        let a = LatLon::new(0.0, 0.0);
+        let b = LatLon::new(0.0, PI_OVER_2 * 2.0);
+        let mid = interpolate(a, b, 0.5);
+        assert!(mid.latitude.is_finite() && mid.longitude.is_finite());
+        assert!((distance(a, mid, 1.0) - PI_OVER_2).abs() < 1e-12);
+        assert!((distance(mid, b, 1.0) - PI_OVER_2).abs() < 1e-12);
+    }
+
+    #[test]
+    fn matches_ecef_axes() {
+        // 赤道上と極では測地緯度と地心緯度が一致するので方向も一致する
+        for (lat, lon) in [(0.0, 0.0), (0.0, 0.7), (0.0, -2.0), (PI_OVER_2, 0.0)] {
+            let ll = LatLon::new(lat, lon);
+            let ecef = ll.to_geodetic(0.0).to_ecef().normalize();
+            assert!((ecef_to_direction(ecef) - ll.to_direction()).mag() < 1e-12);
+            assert!((direction_to_ecef(ll.to_direction()) - ecef).mag() < 1e-12);
+        }
+
+        let g = Geodetic::new(0.4, -1.3, 120.0);
+        assert_eq!(LatLon::from(g), LatLon::new(0.4, -1.3));
+    }
+
+    #[test]
+    fn destination_matches_distance_and_bearing() {
+        let start = LatLon::new(0.3, -1.1);
+        let end = destination(start, 0.8, 1500.0, 6371.0);
+        assert!((distance(start, end, 6371.0) - 1500.0).abs() < 1e-6);
+        assert!((initial_bearing(start, end) - 0.8).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,205 @@
+use crate::{joint_limit::JointLimit, quaternion::Quaternion, v3, vector::V3};
+
+// FABRIK (Forward And Backward Reaching Inverse Kinematics)
+// 関節の位置だけを動かして、骨の長さを保ったまま先端を目標に近づける
+pub struct FabrikChain {
+    joints: Vec<V3>,
+    lengths: Vec<f64>,
+    // 関節ごとの、親の骨に対する振りの制限。根元(0番)は無視する
+    // 位置だけを扱うのでねじれは関係なく、twist_axisの代わりに親の骨の向きを使う
+    // swing_axisはワールド座標で、親の骨に垂直な面に射影して楕円の向きを決める
+    limits: Vec<Option<JointLimit>>,
+    pub tolerance: f64,
+    pub max_iterations: usize,
+}
+
+// aからbへの単位ベクトル。同じ位置ならfallbackを使う
+fn direction(a: V3, b: V3, fallback: V3) -> V3 {
+    let d = b - a;
+    let mag = d.mag();
+    if mag < 1e-12 {
+        return fallback;
+    }
+
+    d / mag
+}
+
+// fromをtoに向ける最短の回転。どちらも単位ベクトル
+fn arc(from: V3, to: V3) -> Quaternion {
+    let axis = from.cross(&to);
+    let angle = axis.mag().atan2(from.dot(&to));
+    let axis = axis
+        .try_normalize()
+        .unwrap_or_else(|| from.any_orthonormal_basis().0);
+
+    let half = axis * (angle * 0.5);
+    Quaternion {
+        w: 0.0,
+        x: half.x,
+        y: half.y,
+        z: half.z,
+    }
+    .exp()
+}
+
+impl FabrikChain {
+    pub fn new(joints: Vec<V3>) -> Self {
+        assert!(joints.len() >= 2);
+
+        let lengths = joints.windows(2).map(|w| w[0].distance(&w[1])).collect();
+        let limits = vec![None; joints.len()];
+
+        Self {
+            joints,
+            lengths,
+            limits,
+            tolerance: 1e-6,
+            max_iterations: 16,
+        }
+    }
+
+    // 曲げ角を円形の円錐で制限する
+    pub fn with_limit(self, joint: usize, max_angle: f64) -> Self {
+        self.with_joint_limit(joint, JointLimit::cone(v3!(0.0, 1.0, 0.0), max_angle))
+    }
+
+    pub fn with_joint_limit(mut self, joint: usize, limit: JointLimit) -> Self {
+        self.limits[joint] = Some(limit);
+        self
+    }
+
+    pub fn joints(&self) -> &[V3] {
+        &self.joints
+    }
+
+    pub fn end_effector(&self) -> V3 {
+        self.joints[self.joints.len() - 1]
+    }
+
+    // 根元を固定して先端をtargetに近づける。許容誤差以内に届いたらtrue
+    pub fn solve(&mut self, target: V3) -> bool {
+        let root = self.joints[0];
+        let last = self.joints.len() - 1;
+
+        for _ in 0..self.max_iterations {
+            if self.end_effector().distance(&target) <= self.tolerance {
+                return true;
+            }
+
+            // 先端から根元へ
+            self.joints[last] = target;
+            for i in (0..last).rev() {
+                let fallback = direction(self.joints[i + 1], target, v3!(0.0, 1.0, 0.0));
+                let dir = direction(self.joints[i + 1], self.joints[i], -fallback);
+                self.joints[i] = self.joints[i + 1] + dir * self.lengths[i];
+            }
+
+            // 根元から先端へ。ここで角度の制限をかける
+            self.joints[0] = root;
+            let mut parent = direction(root, self.joints[1], v3!(0.0, 1.0, 0.0));
+            for i in 0..last {
+                let mut dir = direction(self.joints[i], self.joints[i + 1], parent);
+                if let Some(limit) = self.limits[i].filter(|_| i > 0) {
+                    let frame = JointLimit::new(
+                        parent,
+                        limit.swing_axis,
+                        limit.swing_limit,
+                        limit.twist_limit,
+                    )
+                    .with_softness(limit.softness);
+                    dir = frame.clamp_swing(arc(parent, dir)).rotate_vector(parent);
+                }
+                self.joints[i + 1] = self.joints[i] + dir * self.lengths[i];
+                parent = dir;
+            }
+        }
+
+        self.end_effector().distance(&target) <= self.tolerance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain() -> FabrikChain {
+        FabrikChain::new(vec![
+            v3!(0.0, 0.0, 0.0),
+            v3!(0.0, 1.0, 0.0),
+            v3!(0.0, 2.0, 0.0),
+            v3!(0.0, 3.0, 0.0),
+        ])
+    }
+
+    fn lengths_preserved(chain: &FabrikChain) -> bool {
+        chain
+            .joints()
+            .windows(2)
+            .all(|w| (w[0].distance(&w[1]) - 1.0).abs() < 1e-9)
+    }
+
+    #[test]
+    fn reaches_target() {
+        let mut chain = chain();
+        let target = v3!(1.5, 1.5, 0.5);
+        assert!(chain.solve(target));
+        assert!(chain.end_effector().distance(&target) < 1e-6);
+        assert_eq!(chain.joints()[0], v3!(0.0, 0.0, 0.0));
+        assert!(lengths_preserved(&chain));
+    }
+
+    #[test]
+    fn unreachable_target_straightens_chain() {
+        let mut chain = chain();
+        assert!(!chain.solve(v3!(10.0, 0.0, 0.0)));
+        assert!(chain.end_effector().distance(&v3!(3.0, 0.0, 0.0)) < 1e-3);
+        assert!(lengths_preserved(&chain));
+    }
+
+    #[test]
+    fn limits_bend_angle() {
+        let max_angle = 0.3;
+        let mut chain = chain().with_limit(1, max_angle).with_limit(2, max_angle);
+        chain.solve(v3!(2.0, 0.5, 0.0));
+
+        let j = chain.joints();
+        for i in 1..3 {
+            let angle = (j[i] - j[i - 1]).angle_between(&(j[i + 1] - j[i]));
+            assert!(angle <= max_angle + 1e-9);
+        }
+        assert!(lengths_preserved(&chain));
+    }
+
+    #[test]
+    fn elliptical_limit() {
+        // x軸まわり(yz平面内の曲げ)は0.2、z軸まわり(xy平面内の曲げ)は0.6まで
+        let limit = JointLimit::new(
+            v3!(0.0, 1.0, 0.0),
+            v3!(1.0, 0.0, 0.0),
+            (0.2, 0.6),
+            (0.0, 0.0),
+        );
+        let mut bent = chain()
+            .with_joint_limit(1, limit)
+            .with_joint_limit(2, limit);
+        bent.solve(v3!(2.0, 1.0, 0.0));
+
+        let j = bent.joints();
+        for i in 1..3 {
+            let angle = (j[i] - j[i - 1]).angle_between(&(j[i + 1] - j[i]));
+            assert!(angle <= 0.6 + 1e-9);
+        }
+        assert!(lengths_preserved(&bent));
+
+        // 同じ曲げでもyz平面内では0.2まで
+        let mut chain = chain()
+            .with_joint_limit(1, limit)
+            .with_joint_limit(2, limit);
+        chain.solve(v3!(0.0, 1.0, 2.0));
+        let j = chain.joints();
+        for i in 1..3 {
+            let angle = (j[i] - j[i - 1]).angle_between(&(j[i + 1] - j[i]));
+            assert!(angle <= 0.2 + 1e-9);
+        }
+    }
+}
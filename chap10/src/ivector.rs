@@ -0,0 +1,213 @@
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use crate::{v3, vector::V3, vector2::V2};
+
+// ボクセルやタイルのセル座標などに使う整数ベクトル
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct IV3 {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct IV2 {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl IV3 {
+    pub fn new(x: i32, y: i32, z: i32) -> Self {
+        IV3 { x, y, z }
+    }
+
+    // セル座標を求めるときはfloor。負の側で0に丸めないように注意
+    pub fn from_floor(v: V3) -> Self {
+        IV3::new(v.x.floor() as i32, v.y.floor() as i32, v.z.floor() as i32)
+    }
+
+    pub fn from_round(v: V3) -> Self {
+        IV3::new(v.x.round() as i32, v.y.round() as i32, v.z.round() as i32)
+    }
+
+    pub fn from_ceil(v: V3) -> Self {
+        IV3::new(v.x.ceil() as i32, v.y.ceil() as i32, v.z.ceil() as i32)
+    }
+
+    pub fn to_v3(&self) -> V3 {
+        v3!(self.x as f64, self.y as f64, self.z as f64)
+    }
+
+    pub fn min(&self, other: &Self) -> Self {
+        IV3::new(
+            self.x.min(other.x),
+            self.y.min(other.y),
+            self.z.min(other.z),
+        )
+    }
+
+    pub fn max(&self, other: &Self) -> Self {
+        IV3::new(
+            self.x.max(other.x),
+            self.y.max(other.y),
+            self.z.max(other.z),
+        )
+    }
+}
+
+impl IV2 {
+    pub fn new(x: i32, y: i32) -> Self {
+        IV2 { x, y }
+    }
+
+    pub fn from_floor(v: V2) -> Self {
+        IV2::new(v.x.floor() as i32, v.y.floor() as i32)
+    }
+
+    pub fn from_round(v: V2) -> Self {
+        IV2::new(v.x.round() as i32, v.y.round() as i32)
+    }
+
+    pub fn from_ceil(v: V2) -> Self {
+        IV2::new(v.x.ceil() as i32, v.y.ceil() as i32)
+    }
+
+    pub fn to_v2(&self) -> V2 {
+        V2::new(self.x as f64, self.y as f64)
+    }
+
+    pub fn min(&self, other: &Self) -> Self {
+        IV2::new(self.x.min(other.x), self.y.min(other.y))
+    }
+
+    pub fn max(&self, other: &Self) -> Self {
+        IV2::new(self.x.max(other.x), self.y.max(other.y))
+    }
+}
+
+impl From<IV3> for V3 {
+    fn from(v: IV3) -> Self {
+        v.to_v3()
+    }
+}
+
+impl From<IV2> for V2 {
+    fn from(v: IV2) -> Self {
+        v.to_v2()
+    }
+}
+
+impl Add for IV3 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        IV3::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl AddAssign for IV3 {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for IV3 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        IV3::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl SubAssign for IV3 {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for IV3 {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        IV3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl Mul<i32> for IV3 {
+    type Output = Self;
+
+    fn mul(self, rhs: i32) -> Self::Output {
+        IV3::new(self.x * rhs, self.y * rhs, self.z * rhs)
+    }
+}
+
+impl Add for IV2 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        IV2::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl AddAssign for IV2 {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for IV2 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        IV2::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl SubAssign for IV2 {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for IV2 {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        IV2::new(-self.x, -self.y)
+    }
+}
+
+impl Mul<i32> for IV2 {
+    type Output = Self;
+
+    fn mul(self, rhs: i32) -> Self::Output {
+        IV2::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        let a = IV3::new(1, -2, 3);
+        let b = IV3::new(4, 5, -6);
+        assert_eq!(a + b, IV3::new(5, 3, -3));
+        assert_eq!(a - b, IV3::new(-3, -7, 9));
+        assert_eq!(-a * 2, IV3::new(-2, 4, -6));
+        assert_eq!(a.min(&b), IV3::new(1, -2, -6));
+        assert_eq!(a.max(&b), IV3::new(4, 5, 3));
+        assert_eq!(IV2::new(1, 2) + IV2::new(3, 4), IV2::new(4, 6));
+    }
+
+    #[test]
+    fn conversions() {
+        let v = v3!(1.5, -1.5, 2.2);
+        assert_eq!(IV3::from_floor(v), IV3::new(1, -2, 2));
+        assert_eq!(IV3::from_round(v), IV3::new(2, -2, 2));
+        assert_eq!(IV3::from_ceil(v), IV3::new(2, -1, 3));
+        assert_eq!(V3::from(IV3::new(1, 2, 3)), v3!(1.0, 2.0, 3.0));
+        assert_eq!(IV2::from_floor(V2::new(-0.5, 0.5)), IV2::new(-1, 0));
+    }
+}
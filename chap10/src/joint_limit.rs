@@ -0,0 +1,201 @@
+use crate::{quaternion::Quaternion, utils::GameMath, v3, vector::V3};
+
+// 関節の回転の制限。ねじれ(twist)は範囲、振り(swing)は楕円の円錐で制限する
+// IKやラグドールの関節に使う
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JointLimit {
+    // 骨の向き。ねじれの回転軸
+    pub twist_axis: V3,
+    // twist_axisに垂直な軸。swing_limitの1つ目はこの軸まわりの振りの上限
+    pub swing_axis: V3,
+    // (swing_axisまわり, twist_axis × swing_axisまわり) の振りの上限
+    pub swing_limit: (f64, f64),
+    // ねじれの角度の (最小, 最大)。[-PI, PI) の範囲で指定する
+    pub twist_limit: (f64, f64),
+    // 制限の手前softnessラジアンから滑らかに飽和させる。0なら硬い制限
+    pub softness: f64,
+}
+
+// 上限limitの手前softnessから指数的にlimitへ近づける。x <= limit - softness はそのまま
+fn soft_min(x: f64, limit: f64, softness: f64) -> f64 {
+    if softness <= 0.0 {
+        return x.min(limit);
+    }
+
+    let knee = limit - softness;
+    if x <= knee {
+        return x;
+    }
+
+    knee + softness * (1.0 - (-(x - knee) / softness).exp())
+}
+
+impl JointLimit {
+    // swing_axisはtwist_axisに垂直な成分だけを使う
+    pub fn new(
+        twist_axis: V3,
+        swing_axis: V3,
+        swing_limit: (f64, f64),
+        twist_limit: (f64, f64),
+    ) -> Self {
+        let twist_axis = twist_axis.normalize();
+        let swing_axis = (swing_axis - twist_axis * swing_axis.dot(&twist_axis))
+            .try_normalize()
+            .unwrap_or_else(|| twist_axis.any_orthonormal_basis().0);
+
+        Self {
+            twist_axis,
+            swing_axis,
+            swing_limit,
+            twist_limit,
+            softness: 0.0,
+        }
+    }
+
+    // 円形の円錐でねじれは自由
+    pub fn cone(twist_axis: V3, max_angle: f64) -> Self {
+        let twist_axis = twist_axis.normalize();
+        Self::new(
+            twist_axis,
+            twist_axis.any_orthonormal_basis().0,
+            (max_angle, max_angle),
+            (-std::f64::consts::PI, std::f64::consts::PI),
+        )
+    }
+
+    pub fn with_softness(mut self, softness: f64) -> Self {
+        self.softness = softness;
+        self
+    }
+
+    // ねじれの角度を範囲に収める
+    pub fn clamp_twist(&self, angle: f64) -> f64 {
+        let (min, max) = self.twist_limit;
+        let softness = self.softness.min((max - min) * 0.5);
+
+        let angle = soft_min(angle, max, softness);
+        -soft_min(-angle, -min, softness)
+    }
+
+    // 振りの四元数を楕円の円錐に収める。回転軸がtwist_axisに垂直であることを前提とする
+    pub fn clamp_swing(&self, swing: Quaternion) -> Quaternion {
+        // 回転ベクトル(回転軸 * 角度)にする。短い方の回転を使う
+        let log = swing.canonicalized().log();
+        let r = v3!(log.x, log.y, log.z) * 2.0;
+        let angle = r.mag();
+        if angle == 0.0 {
+            return swing;
+        }
+
+        // 回転軸の向きでの楕円の半径
+        let u = self.swing_axis;
+        let v = self.twist_axis.cross(&self.swing_axis);
+        let term = |c: f64, limit: f64| if c == 0.0 { 0.0 } else { (c / limit).powi(2) };
+        let radius = 1.0
+            / (term(r.dot(&u) / angle, self.swing_limit.0)
+                + term(r.dot(&v) / angle, self.swing_limit.1))
+            .sqrt();
+
+        let clamped = soft_min(angle, radius, self.softness.min(radius));
+        let half = r * (0.5 * clamped / angle);
+        Quaternion {
+            w: 0.0,
+            x: half.x,
+            y: half.y,
+            z: half.z,
+        }
+        .exp()
+    }
+
+    // 振りとねじれに分解してそれぞれを制限し、合成し直す
+    pub fn clamp(&self, q: &Quaternion) -> Quaternion {
+        let (swing, twist) = q.swing_twist(self.twist_axis);
+
+        let d =
+            twist.x * self.twist_axis.x + twist.y * self.twist_axis.y + twist.z * self.twist_axis.z;
+        let angle = (2.0 * d.atan2(twist.w)).wrap_pi();
+        let half = self.twist_axis * (0.5 * self.clamp_twist(angle));
+        let twist = Quaternion {
+            w: 0.0,
+            x: half.x,
+            y: half.y,
+            z: half.z,
+        }
+        .exp();
+
+        twist * self.clamp_swing(swing)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rotated(axis: V3, theta: f64) -> Quaternion {
+        let mut q = Quaternion::identitiy();
+        q.rotate_axis(axis.normalize(), theta);
+        q
+    }
+
+    fn limit() -> JointLimit {
+        JointLimit::new(
+            v3!(0.0, 1.0, 0.0),
+            v3!(1.0, 0.0, 0.0),
+            (0.4, 0.8),
+            (-0.2, 0.5),
+        )
+    }
+
+    #[test]
+    fn inside_limits_unchanged() {
+        let q = rotated(v3!(0.0, 1.0, 0.0), 0.3) * rotated(v3!(1.0, 0.0, 1.0), 0.3);
+        assert!(limit().clamp(&q).approx_eq_rotation(&q, 1e-12));
+    }
+
+    #[test]
+    fn twist_clamped_to_range() {
+        let l = limit();
+        let q = rotated(v3!(0.0, 1.0, 0.0), 1.2);
+        assert!(l
+            .clamp(&q)
+            .approx_eq_rotation(&rotated(v3!(0.0, 1.0, 0.0), 0.5), 1e-12));
+
+        let q = rotated(v3!(0.0, -1.0, 0.0), 1.2);
+        assert!(l
+            .clamp(&q)
+            .approx_eq_rotation(&rotated(v3!(0.0, 1.0, 0.0), -0.2), 1e-12));
+    }
+
+    #[test]
+    fn swing_clamped_to_ellipse() {
+        let l = limit();
+        // swing_axis(x)まわりは0.4、z軸まわりは0.8まで
+        let x = l.clamp(&rotated(v3!(1.0, 0.0, 0.0), 1.0));
+        assert!(x.approx_eq_rotation(&rotated(v3!(1.0, 0.0, 0.0), 0.4), 1e-12));
+        let z = l.clamp(&rotated(v3!(0.0, 0.0, -1.0), 1.0));
+        assert!(z.approx_eq_rotation(&rotated(v3!(0.0, 0.0, -1.0), 0.8), 1e-12));
+
+        // 斜め方向は楕円上に乗る
+        let c = rotated(v3!(1.0, 0.0, 1.0), 1.5);
+        let (swing, _) = l.clamp(&c).swing_twist(l.twist_axis);
+        let log = swing.log();
+        let (a, b) = (log.x * 2.0, log.z * 2.0);
+        assert!(((a / 0.4).powi(2) + (b / 0.8).powi(2) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn soft_clamp_is_continuous_and_bounded() {
+        let l = limit().with_softness(0.1);
+        let mut prev = l.clamp_twist(0.0);
+        for i in 1..=200 {
+            let angle = l.clamp_twist(i as f64 * 0.01);
+            assert!(angle >= prev && angle < 0.5);
+            assert!(angle - prev <= 0.01 + 1e-12);
+            prev = angle;
+        }
+        // 手前では何もしない
+        assert_eq!(l.clamp_twist(0.35), 0.35);
+        assert_eq!(l.clamp_twist(-0.05), -0.05);
+        assert!(l.clamp_twist(-1.0) > -0.2);
+    }
+}
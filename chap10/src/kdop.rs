@@ -0,0 +1,135 @@
+use crate::{v3, vector::V3};
+
+const FACE_AXES: [(f64, f64, f64); 3] = [(1.0, 0.0, 0.0), (0.0, 1.0, 0.0), (0.0, 0.0, 1.0)];
+const CORNER_AXES: [(f64, f64, f64); 4] = [
+    (1.0, 1.0, 1.0),
+    (1.0, 1.0, -1.0),
+    (1.0, -1.0, 1.0),
+    (-1.0, 1.0, 1.0),
+];
+const EDGE_AXES: [(f64, f64, f64); 6] = [
+    (1.0, 1.0, 0.0),
+    (1.0, 0.0, 1.0),
+    (0.0, 1.0, 1.0),
+    (1.0, -1.0, 0.0),
+    (1.0, 0.0, -1.0),
+    (0.0, 1.0, -1.0),
+];
+
+// 平面の枚数。軸の数はその半分
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KdopKind {
+    K8,
+    K14,
+    K18,
+    K26,
+}
+
+impl KdopKind {
+    // 正規化していない軸。同じ種類のk-DOP同士で比較する限り問題ない
+    pub fn axes(&self) -> Vec<V3> {
+        let sets: &[&[(f64, f64, f64)]] = match self {
+            KdopKind::K8 => &[&CORNER_AXES],
+            KdopKind::K14 => &[&FACE_AXES, &CORNER_AXES],
+            KdopKind::K18 => &[&FACE_AXES, &EDGE_AXES],
+            KdopKind::K26 => &[&FACE_AXES, &CORNER_AXES, &EDGE_AXES],
+        };
+
+        sets.iter()
+            .flat_map(|set| set.iter())
+            .map(|(x, y, z)| v3!(*x, *y, *z))
+            .collect()
+    }
+}
+
+// 固定の軸方向ごとに [min, max] の区間を持つ凸包の近似
+#[derive(Debug, Clone, PartialEq)]
+pub struct Kdop {
+    kind: KdopKind,
+    min: Vec<f64>,
+    max: Vec<f64>,
+}
+
+impl Kdop {
+    pub fn from_points(kind: KdopKind, points: &[V3]) -> Option<Self> {
+        if points.is_empty() {
+            return None;
+        }
+
+        let axes = kind.axes();
+        let mut min = vec![f64::INFINITY; axes.len()];
+        let mut max = vec![f64::NEG_INFINITY; axes.len()];
+
+        for p in points {
+            for (i, axis) in axes.iter().enumerate() {
+                let d = p.dot(axis);
+                min[i] = min[i].min(d);
+                max[i] = max[i].max(d);
+            }
+        }
+
+        Some(Self { kind, min, max })
+    }
+
+    pub fn kind(&self) -> KdopKind {
+        self.kind
+    }
+
+    // 分離軸が1つでもあれば重ならない
+    pub fn overlaps(&self, other: &Kdop) -> bool {
+        assert_eq!(self.kind, other.kind);
+
+        (0..self.min.len()).all(|i| self.min[i] <= other.max[i] && other.min[i] <= self.max[i])
+    }
+
+    pub fn contains(&self, p: &V3) -> bool {
+        self.kind.axes().iter().enumerate().all(|(i, axis)| {
+            let d = p.dot(axis);
+            self.min[i] <= d && d <= self.max[i]
+        })
+    }
+
+    pub fn merge(&self, other: &Kdop) -> Kdop {
+        assert_eq!(self.kind, other.kind);
+
+        Kdop {
+            kind: self.kind,
+            min: self
+                .min
+                .iter()
+                .zip(&other.min)
+                .map(|(a, b)| a.min(*b))
+                .collect(),
+            max: self
+                .max
+                .iter()
+                .zip(&other.max)
+                .map(|(a, b)| a.max(*b))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plane_counts() {
+        assert_eq!(KdopKind::K8.axes().len() * 2, 8);
+        assert_eq!(KdopKind::K14.axes().len() * 2, 14);
+        assert_eq!(KdopKind::K18.axes().len() * 2, 18);
+        assert_eq!(KdopKind::K26.axes().len() * 2, 26);
+    }
+
+    #[test]
+    fn tighter_than_box() {
+        // 対角に並んだ2つの三角形はAABBでは重なるが14-DOPでは分離できる
+        let a = [v3!(0.0, 0.0, 0.0), v3!(1.0, 0.0, 0.0), v3!(0.0, 1.0, 0.0)];
+        let b = [v3!(1.0, 1.0, 0.0), v3!(0.9, 0.9, 0.0), v3!(1.0, 0.9, 0.0)];
+        let a = Kdop::from_points(KdopKind::K14, &a).unwrap();
+        let b = Kdop::from_points(KdopKind::K14, &b).unwrap();
+        assert!(!a.overlaps(&b));
+        assert!(a.merge(&b).contains(&v3!(0.5, 0.5, 0.0)));
+    }
+}
@@ -1,6 +1,39 @@
 pub mod vector;
 pub mod quaternion;
-pub mod quaternion;
 pub mod utils;
 pub mod euler_angles;
 pub mod matrix;
+pub mod filter;
+pub mod stats;
+pub mod damping;
+pub mod timestep;
+pub mod units;
+pub mod kdop;
+pub mod sphere_tree;
+pub mod monte_carlo;
+pub mod pixel_sampling;
+pub mod dual;
+pub mod geodetic;
+pub mod great_circle;
+pub mod audio;
+pub mod light;
+pub mod se3;
+pub mod vector2;
+pub mod vector4;
+pub mod ik;
+pub mod ivector;
+pub mod barycentric;
+pub mod approx;
+pub mod ellipsoid;
+#[cfg(feature = "rand")]
+pub mod random;
+pub mod snap;
+pub mod codec;
+pub mod coordinates;
+pub mod delta_stream;
+pub mod matrix4;
+pub mod ode;
+pub mod ballistic;
+pub mod joint_limit;
+pub mod orientation_track;
+pub mod root_motion;
@@ -0,0 +1,185 @@
+use crate::{
+    matrix::{Matrix3x4, RotationMatrix},
+    v3,
+    vector::V3,
+};
+
+pub enum Light {
+    // directionは光の進む向き
+    Directional {
+        direction: V3,
+    },
+    Point {
+        position: V3,
+        range: f64,
+    },
+    // 角度はコーンの中心軸からの半角
+    Spot {
+        position: V3,
+        direction: V3,
+        range: f64,
+        inner_angle: f64,
+        outer_angle: f64,
+    },
+}
+
+// 光源のごく近くで減衰の分母が0にならないよう、距離をこれ以上に扱う
+pub const MIN_LIGHT_DISTANCE: f64 = 0.01;
+
+// 逆二乗減衰をrangeで滑らかに0にする窓関数付きの距離減衰
+pub fn range_attenuation(distance: f64, range: f64) -> f64 {
+    let ratio = distance / range;
+    let window = (1.0 - ratio * ratio * ratio * ratio).clamp(0.0, 1.0);
+    let distance = distance.max(MIN_LIGHT_DISTANCE);
+
+    window * window / (distance * distance)
+}
+
+// 内側のコーンでは1、外側のコーンの外では0、その間はsmoothstep
+pub fn spot_falloff(cos_angle: f64, inner_angle: f64, outer_angle: f64) -> f64 {
+    let cos_inner = inner_angle.cos();
+    let cos_outer = outer_angle.cos();
+    if cos_inner <= cos_outer {
+        return if cos_angle >= cos_outer { 1.0 } else { 0.0 };
+    }
+
+    let t = ((cos_angle - cos_outer) / (cos_inner - cos_outer)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+// forwardを+z、upに近い向きを+yとするワールド->ライト空間の行列
+fn look_to(position: V3, forward: V3, up: V3) -> Matrix3x4 {
+    let forward = forward.normalize();
+    let right = up.cross(&forward).normalize();
+    let up = forward.cross(&right);
+
+    Matrix3x4::from_parent_to_local_matrix(
+        position,
+        RotationMatrix::from_axis_vectors(right, up, forward),
+    )
+}
+
+// 真上・真下を向いても退化しない上方向
+fn up_hint(forward: V3) -> V3 {
+    if forward.normalize().y.abs() > 0.99 {
+        v3!(0.0, 0.0, 1.0)
+    } else {
+        v3!(0.0, 1.0, 0.0)
+    }
+}
+
+impl Light {
+    // 点pに届く光の強さの係数
+    pub fn attenuation(&self, p: V3) -> f64 {
+        match self {
+            Light::Directional { .. } => 1.0,
+            Light::Point { position, range } => range_attenuation(p.distance(position), *range),
+            Light::Spot {
+                position,
+                direction,
+                range,
+                inner_angle,
+                outer_angle,
+            } => {
+                let offset = p - *position;
+                let distance = offset.mag();
+                // 光源の位置では向きが決まらないので、コーンの内側として点光源と同じ値にする
+                if distance == 0.0 {
+                    return range_attenuation(distance, *range);
+                }
+                let cos_angle = (offset / distance).dot(&direction.normalize());
+
+                range_attenuation(distance, *range)
+                    * spot_falloff(cos_angle, *inner_angle, *outer_angle)
+            }
+        }
+    }
+
+    // シャドウマップ用のワールド->ライト空間(ビュー)行列
+    // 平行光源はcenterを原点に置く。点光源はキューブマップの6面(+x, -x, +y, -y, +z, -z)
+    pub fn light_space_matrices(&self, center: V3) -> Vec<Matrix3x4> {
+        match self {
+            Light::Directional { direction } => {
+                vec![look_to(center, *direction, up_hint(*direction))]
+            }
+            Light::Spot {
+                position,
+                direction,
+                ..
+            } => vec![look_to(*position, *direction, up_hint(*direction))],
+            Light::Point { position, .. } => [
+                v3!(1.0, 0.0, 0.0),
+                v3!(-1.0, 0.0, 0.0),
+                v3!(0.0, 1.0, 0.0),
+                v3!(0.0, -1.0, 0.0),
+                v3!(0.0, 0.0, 1.0),
+                v3!(0.0, 0.0, -1.0),
+            ]
+            .into_iter()
+            .map(|forward| look_to(*position, forward, up_hint(forward)))
+            .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn falloff() {
+        assert_eq!(spot_falloff(1.0, 0.2, 0.4), 1.0);
+        assert_eq!(spot_falloff(0.5f64.cos(), 0.2, 0.4), 0.0);
+        assert_eq!(range_attenuation(10.0, 10.0), 0.0);
+        assert!(range_attenuation(1.0, 10.0) > range_attenuation(2.0, 10.0));
+    }
+
+    #[test]
+    fn spot_light_space() {
+        let light = Light::Spot {
+            position: v3!(1.0, 2.0, 3.0),
+            direction: v3!(1.0, 0.0, 0.0),
+            range: 10.0,
+            inner_angle: 0.2,
+            outer_angle: 0.4,
+        };
+
+        let m = &light.light_space_matrices(v3!(0.0, 0.0, 0.0))[0];
+        let p = v3!(4.0, 2.0, 3.0) * *m;
+        assert!((p - v3!(0.0, 0.0, 3.0)).mag() < 1e-12);
+        assert!(light.attenuation(v3!(4.0, 2.0, 3.0)) > 0.0);
+        assert_eq!(light.attenuation(v3!(1.0, 5.0, 3.0)), 0.0);
+    }
+
+    #[test]
+    fn attenuation_at_light_position() {
+        let position = v3!(1.0, 2.0, 3.0);
+        let point = Light::Point {
+            position,
+            range: 5.0,
+        };
+        let spot = Light::Spot {
+            position,
+            direction: v3!(0.0, -1.0, 0.0),
+            range: 5.0,
+            inner_angle: 0.2,
+            outer_angle: 0.4,
+        };
+
+        let expected = 1.0 / (MIN_LIGHT_DISTANCE * MIN_LIGHT_DISTANCE);
+        assert_eq!(point.attenuation(position), expected);
+        assert_eq!(spot.attenuation(position), expected);
+        // 最小距離の内側では一定
+        let near = position + v3!(0.0, -MIN_LIGHT_DISTANCE * 0.5, 0.0);
+        assert_eq!(spot.attenuation(near), point.attenuation(near));
+    }
+
+    #[test]
+    fn point_light_faces() {
+        let light = Light::Point {
+            position: v3!(0.0, 0.0, 0.0),
+            range: 5.0,
+        };
+        assert_eq!(light.light_space_matrices(v3!(0.0, 0.0, 0.0)).len(), 6);
+    }
+}
@@ -1,13 +1,42 @@
 use std::ops::{Mul, MulAssign};
 
-use crate::{euler_angles::EulerAngles, quaternion::Quaternion, v3, vector::V3};
+use crate::{
+    debug_assert_finite, euler_angles::EulerAngles, quaternion::Quaternion, v3, vector::V3,
+};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Axis {
     X,
     Y,
     Z,
+    NegX,
+    NegY,
+    NegZ,
 }
 
+impl Axis {
+    // 正の軸と符号に分ける
+    pub fn split(&self) -> (Axis, f64) {
+        match self {
+            Axis::X => (Axis::X, 1.0),
+            Axis::Y => (Axis::Y, 1.0),
+            Axis::Z => (Axis::Z, 1.0),
+            Axis::NegX => (Axis::X, -1.0),
+            Axis::NegY => (Axis::Y, -1.0),
+            Axis::NegZ => (Axis::Z, -1.0),
+        }
+    }
+
+    pub fn to_vector(&self) -> V3 {
+        match self.split() {
+            (Axis::X, sign) => v3![sign, 0.0, 0.0],
+            (Axis::Y, sign) => v3![0.0, sign, 0.0],
+            (_, sign) => v3![0.0, 0.0, sign],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Matrix3x4 {
     pub m11: f64,
     pub m12: f64,
@@ -25,7 +54,7 @@ pub struct Matrix3x4 {
 
 // 実質の4x4正方行列。右端は使わないので省略
 impl Matrix3x4 {
-    fn indentity() -> Self {
+    pub fn indentity() -> Self {
         Matrix3x4 {
             m11: 1.0,
             m12: 0.0,
@@ -42,19 +71,19 @@ impl Matrix3x4 {
         }
     }
 
-    fn zero_translation(&mut self) {
+    pub fn zero_translation(&mut self) {
         self.tx = 0.0;
         self.ty = 0.0;
         self.tz = 0.0;
     }
 
-    fn translate(&mut self, d: V3) {
+    pub fn translate(&mut self, d: V3) {
         self.tx = d.x();
         self.ty = d.y();
         self.tz = d.z();
     }
 
-    fn from_translation(d: V3) -> Self {
+    pub fn from_translation(d: V3) -> Self {
         Self {
             tx: d.x,
             ty: d.y,
@@ -63,13 +92,13 @@ impl Matrix3x4 {
         }
     }
 
-    fn from_local_to_parent_euler(pos: V3, orient: EulerAngles) -> Self {
+    pub fn from_local_to_parent_euler(pos: V3, orient: EulerAngles) -> Self {
         let orient_mat = RotationMatrix::from_orientation(orient);
 
         Self::from_local_to_parent_matrix(pos, orient_mat)
     }
 
-    fn from_local_to_parent_matrix(pos: V3, orient: RotationMatrix) -> Self {
+    pub fn from_local_to_parent_matrix(pos: V3, orient: RotationMatrix) -> Self {
         Self {
             m11: orient.m11,
             m12: orient.m21,
@@ -85,13 +114,13 @@ impl Matrix3x4 {
             tz: pos.z,
         }
     }
-    fn from_parent_to_local_euler(pos: V3, orient: EulerAngles) -> Self {
+    pub fn from_parent_to_local_euler(pos: V3, orient: EulerAngles) -> Self {
         let orient_mat = RotationMatrix::from_orientation(orient);
 
         Self::from_parent_to_local_matrix(pos, orient_mat)
     }
 
-    fn from_parent_to_local_matrix(pos: V3, orient: RotationMatrix) -> Self {
+    pub fn from_parent_to_local_matrix(pos: V3, orient: RotationMatrix) -> Self {
         Self {
             m11: orient.m11,
             m12: orient.m12,
@@ -108,8 +137,10 @@ impl Matrix3x4 {
         }
     }
 
-    fn from_rotate(axis: Axis, theta: f64) -> Self {
-        let (s, c) = theta.sin_cos();
+    pub fn from_rotate(axis: Axis, theta: f64) -> Self {
+        // 負の軸まわりの回転は逆回転
+        let (axis, sign) = axis.split();
+        let (s, c) = (theta * sign).sin_cos();
 
         match axis {
             Axis::X => Self {
@@ -126,7 +157,7 @@ impl Matrix3x4 {
                 m33: c,
                 ..Self::indentity()
             },
-            Axis::Z => Self {
+            _ => Self {
                 m11: c,
                 m12: s,
                 m21: -s,
@@ -136,7 +167,10 @@ impl Matrix3x4 {
         }
     }
 
-    fn from_rotate_by(axis: V3, theta: f64) -> Self {
+    pub fn from_rotate_by(axis: V3, theta: f64) -> Self {
+        debug_assert_finite!(axis);
+        debug_assert_finite!(theta);
+
         let (s, c) = theta.sin_cos();
 
         let a = 1.0 - c;
@@ -158,7 +192,9 @@ impl Matrix3x4 {
         }
     }
 
-    fn from_quaternion(q: Quaternion) -> Self {
+    pub fn from_quaternion(q: Quaternion) -> Self {
+        debug_assert_finite!(q);
+
         let ww = 2.0 * q.w;
         let xx = 2.0 * q.x;
         let yy = 2.0 * q.y;
@@ -167,7 +203,7 @@ impl Matrix3x4 {
         Self {
             m11: 1.0 - yy * q.y - zz * q.z,
             m12: xx * q.y + ww * q.z,
-            m13: xx * q.z - ww * q.x,
+            m13: xx * q.z - ww * q.y,
             m21: xx * q.y - ww * q.z,
             m22: 1.0 - xx * q.x - zz * q.z,
             m23: yy * q.z + ww * q.x,
@@ -178,7 +214,7 @@ impl Matrix3x4 {
         }
     }
 
-    fn from_scale(s: V3) -> Self {
+    pub fn from_scale(s: V3) -> Self {
         Self {
             m11: s.x,
             m22: s.y,
@@ -187,7 +223,7 @@ impl Matrix3x4 {
         }
     }
 
-    fn from_scale_along_axis(axis: V3, k: f64) -> Self {
+    pub fn from_scale_along_axis(axis: V3, k: f64) -> Self {
         let a = k - 1.0;
         let ax = a * axis.x;
         let ay = a * axis.y;
@@ -207,7 +243,13 @@ impl Matrix3x4 {
         }
     }
 
-    fn from_shear(axis: Axis, s: f64, t: f64) -> Self {
+    // axis方向の座標に比例して残りの2軸をs, tだけずらす
+    // 負の軸はその軸の負方向の座標に比例させるので、s, tの符号を反転したものと同じになる
+    pub fn from_shear(axis: Axis, s: f64, t: f64) -> Self {
+        let (axis, sign) = axis.split();
+        let s = s * sign;
+        let t = t * sign;
+
         match axis {
             Axis::X => Self {
                 m12: s,
@@ -219,7 +261,7 @@ impl Matrix3x4 {
                 m23: t,
                 ..Self::indentity()
             },
-            Axis::Z => Self {
+            _ => Self {
                 m31: s,
                 m32: t,
                 ..Self::indentity()
@@ -227,7 +269,7 @@ impl Matrix3x4 {
         }
     }
 
-    fn from_project(n: V3) -> Self {
+    pub fn from_project(n: V3) -> Self {
         Self {
             m11: 1.0 - n.x * n.x,
             m22: 1.0 - n.y * n.y,
@@ -242,7 +284,11 @@ impl Matrix3x4 {
         }
     }
 
-    fn from_reflect(axis: Axis, k: f64) -> Self {
+    pub fn from_reflect(axis: Axis, k: f64) -> Self {
+        // 負の軸では平面の位置も反転する
+        let (axis, sign) = axis.split();
+        let k = k * sign;
+
         match axis {
             Axis::X => Self {
                 m11: -1.0,
@@ -254,7 +300,7 @@ impl Matrix3x4 {
                 ty: 2.0 * k,
                 ..Self::indentity()
             },
-            Axis::Z => Self {
+            _ => Self {
                 m33: -1.0,
                 tz: 2.0 * k,
                 ..Self::indentity()
@@ -262,7 +308,7 @@ impl Matrix3x4 {
         }
     }
 
-    fn from_reflect_by(n: V3) -> Self {
+    pub fn from_reflect_by(n: V3) -> Self {
         let ax = -2.0 * n.x;
         let ay = -2.0 * n.y;
         let az = -2.0 * n.z;
@@ -281,7 +327,37 @@ impl Matrix3x4 {
         }
     }
 
-    fn determinant(&self) -> f64 {
+    // 軸の付け替え。v * m で物体のx, y, zがそれぞれright, up, forwardに移る
+    // RotationMatrix::from_axesと違い、手系が変わる鏡映(行列式が-1)も表せる
+    pub fn from_axes(right: Axis, up: Axis, forward: Axis) -> Self {
+        let (r, u, f) = axis_vectors(right, up, forward);
+
+        Self {
+            m11: r.x,
+            m12: r.y,
+            m13: r.z,
+            m21: u.x,
+            m22: u.y,
+            m23: u.z,
+            m31: f.x,
+            m32: f.y,
+            m33: f.z,
+            ..Self::indentity()
+        }
+    }
+
+    // Z-up右手系(x: 右, y: 前, z: 上)からこの本のY-up左手系(x: 右, y: 上, z: 前)へ
+    // yとzの入れ替えなので自身が逆変換になる。手系が変わるので回転ではなく鏡映
+    pub fn z_up_to_y_up() -> Self {
+        Self::from_axes(Axis::X, Axis::Z, Axis::Y)
+    }
+
+    // z_up_to_y_upの逆
+    pub fn y_up_to_z_up() -> Self {
+        Self::from_axes(Axis::X, Axis::Z, Axis::Y)
+    }
+
+    pub fn determinant(&self) -> f64 {
         self.m11 * (self.m22 * self.m33 - self.m23 * self.m32)
             + self.m12 * (self.m23 * self.m31 - self.m21 * self.m33)
             + self.m13 * (self.m21 * self.m32 - self.m22 * self.m31)
@@ -312,11 +388,354 @@ impl Matrix3x4 {
         result
     }
 
-    fn get_translation(&self) -> V3 {
-        v3![self.tx, self.ty, self.ty,]
+    pub fn is_finite(&self) -> bool {
+        self.elements().all(f64::is_finite)
+    }
+
+    pub fn is_nan(&self) -> bool {
+        self.elements().any(f64::is_nan)
+    }
+
+    // 行優先の配列。最後の行が平行移動
+    pub fn to_array(&self) -> [[f64; 3]; 4] {
+        [
+            [self.m11, self.m12, self.m13],
+            [self.m21, self.m22, self.m23],
+            [self.m31, self.m32, self.m33],
+            [self.tx, self.ty, self.tz],
+        ]
+    }
+
+    pub fn from_array(a: [[f64; 3]; 4]) -> Self {
+        Self {
+            m11: a[0][0],
+            m12: a[0][1],
+            m13: a[0][2],
+            m21: a[1][0],
+            m22: a[1][1],
+            m23: a[1][2],
+            m31: a[2][0],
+            m32: a[2][1],
+            m33: a[2][2],
+            tx: a[3][0],
+            ty: a[3][1],
+            tz: a[3][2],
+        }
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = [f64; 3]> {
+        self.to_array().into_iter()
+    }
+
+    pub fn cols(&self) -> impl Iterator<Item = [f64; 4]> {
+        let a = self.to_array();
+        (0..3).map(move |j| [a[0][j], a[1][j], a[2][j], a[3][j]])
+    }
+
+    // 12要素を行優先で
+    pub fn elements(&self) -> impl Iterator<Item = f64> {
+        self.rows().flatten()
+    }
+
+    pub fn map<F>(&self, f: F) -> Self
+    where
+        F: Fn(f64) -> f64,
+    {
+        Self::from_array(self.to_array().map(|row| row.map(&f)))
+    }
+
+    pub fn zip_map<F>(&self, other: &Matrix3x4, f: F) -> Self
+    where
+        F: Fn(f64, f64) -> f64,
+    {
+        let a = self.to_array();
+        let b = other.to_array();
+
+        Self::from_array(std::array::from_fn(|i| {
+            std::array::from_fn(|j| f(a[i][j], b[i][j]))
+        }))
+    }
+
+    pub fn builder() -> Matrix3x4Builder {
+        Matrix3x4Builder {
+            matrix: Self::indentity(),
+        }
+    }
+
+    // 3x3部分の行同士の内積 M * M^T
+    fn gram(&self) -> [[f64; 3]; 3] {
+        let rows = [
+            v3![self.m11, self.m12, self.m13],
+            v3![self.m21, self.m22, self.m23],
+            v3![self.m31, self.m32, self.m33],
+        ];
+
+        let mut result = [[0.0; 3]; 3];
+        for (i, a) in rows.iter().enumerate() {
+            for (j, b) in rows.iter().enumerate() {
+                result[i][j] = a.x * b.x + a.y * b.y + a.z * b.z;
+            }
+        }
+
+        result
+    }
+
+    // 3x3部分の対角成分の和
+    pub fn trace(&self) -> f64 {
+        self.m11 + self.m22 + self.m33
+    }
+
+    // 3x3部分の全要素の二乗和の平方根
+    pub fn frobenius_norm(&self) -> f64 {
+        self.rows()
+            .take(3)
+            .flatten()
+            .map(|e| e * e)
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    // 3x3部分の最大特異値。M * M^T の最大固有値をべき乗法で求めて平方根を取る
+    pub fn largest_singular_value(&self, iterations: usize) -> f64 {
+        let g = self.gram();
+        let apply = |v: V3| {
+            v3![
+                g[0][0] * v.x + g[0][1] * v.y + g[0][2] * v.z,
+                g[1][0] * v.x + g[1][1] * v.y + g[1][2] * v.z,
+                g[2][0] * v.x + g[2][1] * v.y + g[2][2] * v.z,
+            ]
+        };
+
+        // 特定の固有ベクトルと直交しにくい初期値
+        let mut v = v3![1.0, 0.5, 0.25].normalize();
+        let mut eigenvalue = 0.0;
+        for _ in 0..iterations.max(1) {
+            let next = apply(v);
+            eigenvalue = next.mag();
+            if eigenvalue == 0.0 {
+                return 0.0;
+            }
+            v = next / eigenvalue;
+        }
+
+        eigenvalue.sqrt()
+    }
+
+    // 3x3部分が正規直交(回転または鏡映のみ)か
+    pub fn is_orthogonal(&self, epsilon: f64) -> bool {
+        let g = self.gram();
+
+        (0..3).all(|i| {
+            (0..3).all(|j| {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                (g[i][j] - expected).abs() <= epsilon
+            })
+        })
+    }
+
+    // 3x3部分が回転×一様スケールか
+    pub fn is_uniform_scale(&self, epsilon: f64) -> bool {
+        let g = self.gram();
+        let s = (g[0][0] + g[1][1] + g[2][2]) / 3.0;
+
+        (0..3).all(|i| {
+            (0..3).all(|j| {
+                let expected = if i == j { s } else { 0.0 };
+                (g[i][j] - expected).abs() <= epsilon
+            })
+        })
+    }
+
+    // 行列式が負なら鏡映を含む
+    pub fn has_mirroring(&self) -> bool {
+        self.determinant() < 0.0
+    }
+
+    // フロベニウスノルムによる条件数。2ノルムの条件数以上、その3倍以下になる
+    pub fn condition_number_estimate(&self) -> f64 {
+        let det = self.determinant();
+        if det == 0.0 {
+            return f64::INFINITY;
+        }
+
+        let norm = self.frobenius_norm();
+
+        // 余因子行列のノルム / |det| が逆行列のノルム
+        let adjugate_norm = [
+            self.m22 * self.m33 - self.m23 * self.m32,
+            self.m13 * self.m32 - self.m12 * self.m33,
+            self.m12 * self.m23 - self.m13 * self.m22,
+            self.m23 * self.m31 - self.m21 * self.m33,
+            self.m11 * self.m33 - self.m13 * self.m31,
+            self.m13 * self.m21 - self.m11 * self.m23,
+            self.m21 * self.m32 - self.m22 * self.m31,
+            self.m12 * self.m31 - self.m11 * self.m32,
+            self.m11 * self.m22 - self.m12 * self.m21,
+        ]
+        .iter()
+        .map(|e| e * e)
+        .sum::<f64>()
+        .sqrt();
+
+        norm * adjugate_norm / det.abs()
+    }
+
+    // 3x3部分を 伸縮S * 回転R に極分解する(行ベクトルなので伸縮が先)
+    // R_{k+1} = (R_k + R_k^-T) / 2 を収束するまで繰り返す
+    fn polar_decompose(&self) -> (Matrix3x4, Matrix3x4) {
+        let mut r = Self {
+            tx: 0.0,
+            ty: 0.0,
+            tz: 0.0,
+            ..*self
+        };
+
+        for _ in 0..32 {
+            let det = r.determinant();
+            if det == 0.0 {
+                break;
+            }
+            let one_over_det = 1.0 / det;
+
+            // 余因子行列 / det が逆行列の転置
+            let next = Self {
+                m11: 0.5 * (r.m11 + (r.m22 * r.m33 - r.m23 * r.m32) * one_over_det),
+                m12: 0.5 * (r.m12 + (r.m23 * r.m31 - r.m21 * r.m33) * one_over_det),
+                m13: 0.5 * (r.m13 + (r.m21 * r.m32 - r.m22 * r.m31) * one_over_det),
+                m21: 0.5 * (r.m21 + (r.m13 * r.m32 - r.m12 * r.m33) * one_over_det),
+                m22: 0.5 * (r.m22 + (r.m11 * r.m33 - r.m13 * r.m31) * one_over_det),
+                m23: 0.5 * (r.m23 + (r.m12 * r.m31 - r.m11 * r.m32) * one_over_det),
+                m31: 0.5 * (r.m31 + (r.m12 * r.m23 - r.m13 * r.m22) * one_over_det),
+                m32: 0.5 * (r.m32 + (r.m13 * r.m21 - r.m11 * r.m23) * one_over_det),
+                m33: 0.5 * (r.m33 + (r.m11 * r.m22 - r.m12 * r.m21) * one_over_det),
+                ..Self::indentity()
+            };
+
+            let delta = (next.m11 - r.m11).abs()
+                + (next.m12 - r.m12).abs()
+                + (next.m13 - r.m13).abs()
+                + (next.m21 - r.m21).abs()
+                + (next.m22 - r.m22).abs()
+                + (next.m23 - r.m23).abs()
+                + (next.m31 - r.m31).abs()
+                + (next.m32 - r.m32).abs()
+                + (next.m33 - r.m33).abs();
+
+            r = next;
+
+            if delta < 1e-12 {
+                break;
+            }
+        }
+
+        // 鏡映を含む場合は回転側の符号を反転し、伸縮側に負のスケールとして持たせる
+        if r.determinant() < 0.0 {
+            r *= Self::from_scale(v3![-1.0, -1.0, -1.0]);
+        }
+
+        // S = M * R^T
+        let s = Self {
+            m11: self.m11 * r.m11 + self.m12 * r.m12 + self.m13 * r.m13,
+            m12: self.m11 * r.m21 + self.m12 * r.m22 + self.m13 * r.m23,
+            m13: self.m11 * r.m31 + self.m12 * r.m32 + self.m13 * r.m33,
+            m21: self.m21 * r.m11 + self.m22 * r.m12 + self.m23 * r.m13,
+            m22: self.m21 * r.m21 + self.m22 * r.m22 + self.m23 * r.m23,
+            m23: self.m21 * r.m31 + self.m22 * r.m32 + self.m23 * r.m33,
+            m31: self.m31 * r.m11 + self.m32 * r.m12 + self.m33 * r.m13,
+            m32: self.m31 * r.m21 + self.m32 * r.m22 + self.m33 * r.m23,
+            m33: self.m31 * r.m31 + self.m32 * r.m32 + self.m33 * r.m33,
+            ..Self::indentity()
+        };
+
+        (s, r)
+    }
+
+    // 伸縮は線形補間、回転はslerp、平行移動は線形補間して合成する
+    pub fn interpolate(&self, other: &Matrix3x4, t: f64) -> Matrix3x4 {
+        let (s0, r0) = self.polar_decompose();
+        let (s1, r1) = other.polar_decompose();
+
+        let q = Quaternion::from_matrix(&r0).slerp(Quaternion::from_matrix(&r1), t);
+        let r = Self::from_quaternion(q);
+
+        let lerp = |a: f64, b: f64| a + (b - a) * t;
+        let s = Self {
+            m11: lerp(s0.m11, s1.m11),
+            m12: lerp(s0.m12, s1.m12),
+            m13: lerp(s0.m13, s1.m13),
+            m21: lerp(s0.m21, s1.m21),
+            m22: lerp(s0.m22, s1.m22),
+            m23: lerp(s0.m23, s1.m23),
+            m31: lerp(s0.m31, s1.m31),
+            m32: lerp(s0.m32, s1.m32),
+            m33: lerp(s0.m33, s1.m33),
+            ..Self::indentity()
+        };
+
+        Self {
+            tx: lerp(self.tx, other.tx),
+            ty: lerp(self.ty, other.ty),
+            tz: lerp(self.tz, other.tz),
+            ..s * r
+        }
+    }
+
+    // 回転+平行移動のみの行列の逆行列。回転部分は転置するだけでよい
+    pub fn inverse_rigid(&self) -> Matrix3x4 {
+        debug_assert!(self.is_orthogonal(1e-6));
+
+        self.transposed_inverse_with_scale(1.0)
+    }
+
+    // 回転×一様スケール+平行移動の逆行列。M^-1 = M^T / s^2
+    pub fn inverse_similarity(&self) -> Matrix3x4 {
+        debug_assert!(self.is_uniform_scale(1e-6));
+
+        let scale_sq = self.m11 * self.m11 + self.m12 * self.m12 + self.m13 * self.m13;
+        self.transposed_inverse_with_scale(1.0 / scale_sq)
+    }
+
+    // 一般の逆行列。行列式がほぼ0で逆行列が存在しないときはNone
+    // 行列式は各行の長さの積で上から抑えられる(アダマールの不等式)ので、
+    // その比で判定してスケールによらず特異かどうかを見る
+    pub fn try_inverse(&self) -> Option<Matrix3x4> {
+        let row_norms = v3![self.m11, self.m12, self.m13].mag()
+            * v3![self.m21, self.m22, self.m23].mag()
+            * v3![self.m31, self.m32, self.m33].mag();
+        let det = self.determinant();
+        if !det.is_finite() || det.abs() <= row_norms * 1e-12 {
+            return None;
+        }
+
+        Some(self.inverse(*self))
+    }
+
+    fn transposed_inverse_with_scale(&self, k: f64) -> Matrix3x4 {
+        let mut result = Self {
+            m11: self.m11 * k,
+            m12: self.m21 * k,
+            m13: self.m31 * k,
+            m21: self.m12 * k,
+            m22: self.m22 * k,
+            m23: self.m32 * k,
+            m31: self.m13 * k,
+            m32: self.m23 * k,
+            m33: self.m33 * k,
+            ..Self::indentity()
+        };
+
+        result.tx = -(self.tx * result.m11 + self.ty * result.m21 + self.tz * result.m31);
+        result.ty = -(self.tx * result.m12 + self.ty * result.m22 + self.tz * result.m32);
+        result.tz = -(self.tx * result.m13 + self.ty * result.m23 + self.tz * result.m33);
+
+        result
+    }
+
+    pub fn get_translation(&self) -> V3 {
+        v3![self.tx, self.ty, self.tz,]
     }
 
-    fn get_position_from_parent_to_local_matrix(&self) -> V3 {
+    pub fn get_position_from_parent_to_local_matrix(&self) -> V3 {
         v3![
             -(self.tx * self.m11 + self.ty * self.m12 + self.tz * self.m13),
             -(self.tx * self.m21 + self.ty * self.m22 + self.tz * self.m23),
@@ -324,11 +743,56 @@ impl Matrix3x4 {
         ]
     }
 
-    fn get_position_from_local_to_parent_matrix(&self) -> V3 {
+    pub fn get_position_from_local_to_parent_matrix(&self) -> V3 {
         v3![self.tx, self.ty, self.tz,]
     }
 }
 
+// 呼び出した順に変換を適用する行列を組み立てる
+// builder().scale(s).rotate_axis(n, theta).translate(t) はスケール→回転→平行移動
+pub struct Matrix3x4Builder {
+    matrix: Matrix3x4,
+}
+
+impl Matrix3x4Builder {
+    pub fn then(mut self, m: Matrix3x4) -> Self {
+        self.matrix *= m;
+        self
+    }
+
+    pub fn scale(self, s: V3) -> Self {
+        self.then(Matrix3x4::from_scale(s))
+    }
+
+    pub fn scale_along_axis(self, axis: V3, k: f64) -> Self {
+        self.then(Matrix3x4::from_scale_along_axis(axis, k))
+    }
+
+    pub fn rotate(self, axis: Axis, theta: f64) -> Self {
+        self.then(Matrix3x4::from_rotate(axis, theta))
+    }
+
+    pub fn rotate_axis(self, axis: V3, theta: f64) -> Self {
+        self.then(Matrix3x4::from_rotate_by(axis, theta))
+    }
+
+    pub fn rotate_quaternion(self, q: Quaternion) -> Self {
+        self.then(Matrix3x4::from_quaternion(q))
+    }
+
+    pub fn shear(self, axis: Axis, s: f64, t: f64) -> Self {
+        self.then(Matrix3x4::from_shear(axis, s, t))
+    }
+
+    pub fn translate(self, d: V3) -> Self {
+        self.then(Matrix3x4::from_translation(d))
+    }
+
+    pub fn build(self) -> Matrix3x4 {
+        self.matrix
+    }
+}
+
 impl Mul<Matrix3x4> for V3 {
     type Output = V3;
 
@@ -349,12 +813,12 @@ impl Mul<Matrix3x4> for Matrix3x4 {
             m11: self.m11 * rhs.m11 + self.m12 * rhs.m21 + self.m13 * rhs.m31,
             m12: self.m11 * rhs.m12 + self.m12 * rhs.m22 + self.m13 * rhs.m32,
             m13: self.m11 * rhs.m13 + self.m12 * rhs.m23 + self.m13 * rhs.m33,
-            m21: self.m21 * rhs.m21 + self.m22 * rhs.m21 + self.m23 * rhs.m31,
-            m22: self.m21 * rhs.m22 + self.m22 * rhs.m22 + self.m23 * rhs.m32,
-            m23: self.m21 * rhs.m23 + self.m22 * rhs.m23 + self.m23 * rhs.m33,
-            m31: self.m31 * rhs.m21 + self.m32 * rhs.m21 + self.m33 * rhs.m31,
-            m32: self.m31 * rhs.m22 + self.m32 * rhs.m22 + self.m33 * rhs.m32,
-            m33: self.m31 * rhs.m23 + self.m32 * rhs.m23 + self.m33 * rhs.m33,
+            m21: self.m21 * rhs.m11 + self.m22 * rhs.m21 + self.m23 * rhs.m31,
+            m22: self.m21 * rhs.m12 + self.m22 * rhs.m22 + self.m23 * rhs.m32,
+            m23: self.m21 * rhs.m13 + self.m22 * rhs.m23 + self.m23 * rhs.m33,
+            m31: self.m31 * rhs.m11 + self.m32 * rhs.m21 + self.m33 * rhs.m31,
+            m32: self.m31 * rhs.m12 + self.m32 * rhs.m22 + self.m33 * rhs.m32,
+            m33: self.m31 * rhs.m13 + self.m32 * rhs.m23 + self.m33 * rhs.m33,
             tx: self.tx * rhs.m11 + self.ty * rhs.m21 + self.tz * rhs.m31 + rhs.tx,
             ty: self.tx * rhs.m12 + self.ty * rhs.m22 + self.tz * rhs.m32 + rhs.ty,
             tz: self.tx * rhs.m13 + self.ty * rhs.m23 + self.tz * rhs.m33 + rhs.tz,
@@ -368,6 +832,7 @@ impl MulAssign<Matrix3x4> for Matrix3x4 {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct RotationMatrix {
     pub m11: f64,
     pub m12: f64,
@@ -381,7 +846,80 @@ pub struct RotationMatrix {
 }
 
 impl RotationMatrix {
-    fn identity() -> Self {
+    pub fn is_finite(&self) -> bool {
+        self.elements().all(f64::is_finite)
+    }
+
+    pub fn is_nan(&self) -> bool {
+        self.elements().any(f64::is_nan)
+    }
+
+    pub fn determinant(&self) -> f64 {
+        self.m11 * (self.m22 * self.m33 - self.m23 * self.m32)
+            + self.m12 * (self.m23 * self.m31 - self.m21 * self.m33)
+            + self.m13 * (self.m21 * self.m32 - self.m22 * self.m31)
+    }
+
+    // 行列式が負なら回転ではなく鏡映
+    pub fn has_mirroring(&self) -> bool {
+        self.determinant() < 0.0
+    }
+
+    pub fn to_array(&self) -> [[f64; 3]; 3] {
+        [
+            [self.m11, self.m12, self.m13],
+            [self.m21, self.m22, self.m23],
+            [self.m31, self.m32, self.m33],
+        ]
+    }
+
+    pub fn from_array(a: [[f64; 3]; 3]) -> Self {
+        Self {
+            m11: a[0][0],
+            m12: a[0][1],
+            m13: a[0][2],
+            m21: a[1][0],
+            m22: a[1][1],
+            m23: a[1][2],
+            m31: a[2][0],
+            m32: a[2][1],
+            m33: a[2][2],
+        }
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = [f64; 3]> {
+        self.to_array().into_iter()
+    }
+
+    pub fn cols(&self) -> impl Iterator<Item = [f64; 3]> {
+        let a = self.to_array();
+        (0..3).map(move |j| [a[0][j], a[1][j], a[2][j]])
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item = f64> {
+        self.rows().flatten()
+    }
+
+    pub fn map<F>(&self, f: F) -> Self
+    where
+        F: Fn(f64) -> f64,
+    {
+        Self::from_array(self.to_array().map(|row| row.map(&f)))
+    }
+
+    pub fn zip_map<F>(&self, other: &RotationMatrix, f: F) -> Self
+    where
+        F: Fn(f64, f64) -> f64,
+    {
+        let a = self.to_array();
+        let b = other.to_array();
+
+        Self::from_array(std::array::from_fn(|i| {
+            std::array::from_fn(|j| f(a[i][j], b[i][j]))
+        }))
+    }
+
+    pub fn identity() -> Self {
         RotationMatrix {
             m11: 1.0,
             m12: 0.0,
@@ -395,7 +933,36 @@ impl RotationMatrix {
         }
     }
 
-    fn from_orientation(orientation: EulerAngles) -> Self {
+    // 物体の右・上・前方向を慣性空間のベクトルで与える。各列がそれぞれの軸になる
+    pub fn from_axis_vectors(right: V3, up: V3, forward: V3) -> Self {
+        debug_assert_finite!(right);
+        debug_assert_finite!(up);
+        debug_assert_finite!(forward);
+
+        Self {
+            m11: right.x,
+            m12: up.x,
+            m13: forward.x,
+            m21: right.y,
+            m22: up.y,
+            m23: forward.y,
+            m31: right.z,
+            m32: up.z,
+            m33: forward.z,
+        }
+    }
+
+    // 座標系の取り違えを明示的に直すための軸の付け替え
+    // 例: from_axes(Axis::NegZ, Axis::Y, Axis::X) はyまわりの90°回転
+    // 回転になる組み合わせだけを受け付ける。手系が変わる付け替えはMatrix3x4::from_axesで作る
+    pub fn from_axes(right: Axis, up: Axis, forward: Axis) -> Self {
+        let (r, u, f) = axis_vectors(right, up, forward);
+        assert!(r.cross(&u).dot(&f) > 0.0);
+
+        Self::from_axis_vectors(r, u, f)
+    }
+
+    pub fn from_orientation(orientation: EulerAngles) -> Self {
         let p = orientation.pitch.sin_cos();
         let b = orientation.bank.sin_cos();
         let h = orientation.heading.sin_cos();
@@ -413,7 +980,7 @@ impl RotationMatrix {
         }
     }
 
-    fn from_inertial_to_obj_quaternion(q: Quaternion) -> Self {
+    pub fn from_inertial_to_obj_quaternion(q: Quaternion) -> Self {
         Self {
             m11: 1.0 - 2.0 * (q.y * q.y + q.z * q.z),
             m12: 2.0 * (q.x * q.y + q.w * q.z),
@@ -427,7 +994,7 @@ impl RotationMatrix {
         }
     }
 
-    fn from_obj_to_inertial_quaternion(&self, q: Quaternion) -> Self {
+    pub fn from_obj_to_inertial_quaternion(q: Quaternion) -> Self {
         Self {
             m11: 1.0 - 2.0 * (q.y * q.y + q.z * q.z),
             m12: 2.0 * (q.x * q.y - q.w * q.z),
@@ -441,7 +1008,7 @@ impl RotationMatrix {
         }
     }
 
-    fn inertial_to_obj(&self, v: V3) -> V3 {
+    pub fn inertial_to_obj(&self, v: V3) -> V3 {
         v3![
             self.m11 * v.x + self.m21 * v.y + self.m31 * v.z,
             self.m12 * v.x + self.m22 * v.y + self.m32 * v.z,
@@ -449,11 +1016,294 @@ impl RotationMatrix {
         ]
     }
 
-    fn obj_to_inertial(&self, v: V3) -> V3 {
+    pub fn obj_to_inertial(&self, v: V3) -> V3 {
         v3![
             self.m11 * v.x + self.m12 * v.y + self.m13 * v.z,
-            self.m31 * v.x + self.m22 * v.y + self.m23 * v.z,
+            self.m21 * v.x + self.m22 * v.y + self.m23 * v.z,
             self.m31 * v.x + self.m32 * v.y + self.m33 * v.z,
         ]
     }
 }
+
+// 3つの軸が互いに異なることを確かめてベクトルにする
+fn axis_vectors(right: Axis, up: Axis, forward: Axis) -> (V3, V3, V3) {
+    assert!(right.split().0 != up.split().0);
+    assert!(up.split().0 != forward.split().0);
+    assert!(forward.split().0 != right.split().0);
+
+    (right.to_vector(), up.to_vector(), forward.to_vector())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{approx::ApproxEq, utils::PI_OVER_2};
+
+    fn close(a: &Matrix3x4, b: &Matrix3x4) -> bool {
+        a.elements()
+            .zip(b.elements())
+            .all(|(x, y)| (x - y).abs() < 1e-9)
+    }
+
+    #[test]
+    fn signed_axis_rotation() {
+        let a = Matrix3x4::from_rotate(Axis::NegY, 0.5);
+        let b = Matrix3x4::from_rotate(Axis::Y, -0.5);
+        assert_eq!([a.m11, a.m13, a.m31, a.m33], [b.m11, b.m13, b.m31, b.m33]);
+    }
+
+    #[test]
+    fn builder_applies_in_call_order() {
+        let m = Matrix3x4::builder()
+            .scale(v3![2.0, 2.0, 2.0])
+            .rotate(Axis::Z, PI_OVER_2)
+            .translate(v3![1.0, 0.0, 0.0])
+            .build();
+
+        let p = v3![1.0, 0.0, 0.0] * m;
+        assert!((p - v3![1.0, 2.0, 0.0]).mag() < 1e-12);
+    }
+
+    #[test]
+    fn fast_inverses() {
+        let p = v3![1.0, -2.0, 3.0];
+
+        let rigid = Matrix3x4::builder()
+            .rotate_axis(v3![0.0, 0.6, 0.8], 1.2)
+            .translate(v3![4.0, 5.0, 6.0])
+            .build();
+        assert!((p * rigid * rigid.inverse_rigid() - p).mag() < 1e-12);
+
+        let similarity = Matrix3x4::builder()
+            .scale(v3![3.0, 3.0, 3.0])
+            .rotate(Axis::X, 0.7)
+            .translate(v3![-1.0, 0.0, 2.0])
+            .build();
+        assert!((p * similarity * similarity.inverse_similarity() - p).mag() < 1e-12);
+    }
+
+    #[test]
+    fn element_iterators() {
+        let m = Matrix3x4::from_translation(v3![1.0, 2.0, 3.0]);
+        assert_eq!(m.elements().sum::<f64>(), 9.0);
+        assert_eq!(m.rows().last(), Some([1.0, 2.0, 3.0]));
+        assert_eq!(m.cols().next(), Some([1.0, 0.0, 0.0, 1.0]));
+
+        let doubled = m.map(|e| e * 2.0);
+        let sum = m.zip_map(&doubled, |a, b| a + b);
+        assert_eq!(sum.to_array()[3], [3.0, 6.0, 9.0]);
+    }
+
+    #[test]
+    fn norms() {
+        let m = Matrix3x4::from_scale(v3![3.0, -4.0, 0.5]);
+        assert_eq!(m.trace(), -0.5);
+        assert_eq!(m.frobenius_norm(), (9.0f64 + 16.0 + 0.25).sqrt());
+        assert!((m.largest_singular_value(64) - 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn z_up_to_y_up() {
+        let m = Matrix3x4::z_up_to_y_up();
+        assert_eq!(v3![1.0, 2.0, 3.0] * m, v3![1.0, 3.0, 2.0]);
+        assert_eq!(
+            v3![1.0, 3.0, 2.0] * Matrix3x4::y_up_to_z_up(),
+            v3![1.0, 2.0, 3.0]
+        );
+
+        // 手系が変わるので鏡映
+        assert!(m.has_mirroring());
+        assert_eq!(m.determinant(), -1.0);
+        assert!(!RotationMatrix::from_axes(Axis::NegZ, Axis::Y, Axis::X).has_mirroring());
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_axes_rejects_reflection() {
+        RotationMatrix::from_axes(Axis::X, Axis::Z, Axis::Y);
+    }
+
+    #[test]
+    fn shear_negative_axis() {
+        let p = v3![2.0, 1.0, -1.0];
+        let pos = Matrix3x4::from_shear(Axis::X, 0.5, -0.25);
+        let neg = Matrix3x4::from_shear(Axis::NegX, 0.5, -0.25);
+        assert_eq!(p * pos, v3![2.0, 2.0, -1.5]);
+        // -x方向の座標(-2)に比例してずれる
+        assert_eq!(p * neg, v3![2.0, 0.0, -0.5]);
+    }
+
+    #[test]
+    fn from_axes() {
+        let m = RotationMatrix::from_axes(Axis::NegZ, Axis::Y, Axis::X);
+        assert_eq!(m.obj_to_inertial(v3![1.0, 0.0, 0.0]), v3![0.0, 0.0, -1.0]);
+        assert_eq!(m.obj_to_inertial(v3![0.0, 0.0, 1.0]), v3![1.0, 0.0, 0.0]);
+
+        // 回転になる組み合わせでは、どちらの型でも同じ付け替えになる
+        let p = v3![1.0, 2.0, 3.0];
+        let t = Matrix3x4::from_axes(Axis::NegZ, Axis::Y, Axis::X);
+        assert_eq!(p * t, m.obj_to_inertial(p));
+    }
+
+    #[test]
+    fn finite_checks() {
+        let mut m = Matrix3x4::builder().build();
+        assert!(m.is_finite() && !m.is_nan());
+        m.ty = f64::NAN;
+        assert!(m.is_nan() && !m.is_finite());
+
+        let q = Quaternion {
+            w: f64::INFINITY,
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        assert!(!q.is_finite() && !q.is_nan());
+    }
+
+    #[test]
+    fn factor_validation() {
+        let rotation = Matrix3x4::from_rotate_by(v3![0.0, 0.6, 0.8], 0.9)
+            * Matrix3x4::from_translation(v3![1.0, 2.0, 3.0]);
+        assert!(rotation.is_orthogonal(1e-9));
+        assert!(rotation.is_uniform_scale(1e-9));
+        assert!(!rotation.has_mirroring());
+
+        let uniform =
+            Matrix3x4::from_scale(v3![2.0, 2.0, 2.0]) * Matrix3x4::from_rotate(Axis::X, 0.7);
+        assert!(!uniform.is_orthogonal(1e-9));
+        assert!(uniform.is_uniform_scale(1e-9));
+
+        let scale =
+            Matrix3x4::from_scale(v3![1.0, 2.0, 4.0]) * Matrix3x4::from_rotate(Axis::X, 0.7);
+        assert!(!scale.is_orthogonal(1e-9));
+        assert!(!scale.is_uniform_scale(1e-9));
+        assert!(!scale.has_mirroring());
+
+        let reflection = Matrix3x4::from_reflect(Axis::X, 1.0);
+        assert!(reflection.is_orthogonal(1e-9));
+        assert!(reflection.has_mirroring());
+
+        // 直交行列はフロベニウスノルムでは √3 * √3 = 3
+        let rotation = Matrix3x4::from_rotate_by(v3![0.0, 0.6, 0.8], 0.9);
+        assert!((rotation.condition_number_estimate() - 3.0).abs() < 1e-9);
+        assert!((reflection.condition_number_estimate() - 3.0).abs() < 1e-9);
+        // diag(1, 2, 4) は √21 * √(1 + 1/4 + 1/16)。2ノルムの条件数4以上、その3倍以下
+        let k = scale.condition_number_estimate();
+        assert!((k - 21f64.sqrt() * 1.3125f64.sqrt()).abs() < 1e-9);
+        assert!((4.0..=12.0).contains(&k));
+        assert_eq!(
+            Matrix3x4::from_scale(v3![1.0, 0.0, 1.0]).condition_number_estimate(),
+            f64::INFINITY
+        );
+    }
+
+    #[test]
+    fn interpolate_endpoints() {
+        let a = Matrix3x4::from_scale(v3![1.0, 2.0, 3.0])
+            * Matrix3x4::from_rotate(Axis::Y, 0.3)
+            * Matrix3x4::from_translation(v3![1.0, 2.0, 3.0]);
+        let b = Matrix3x4::from_scale(v3![2.0, 2.0, 1.0])
+            * Matrix3x4::from_rotate(Axis::Y, 1.3)
+            * Matrix3x4::from_translation(v3![3.0, 2.0, 3.0]);
+        assert!(close(&a.interpolate(&b, 0.0), &a));
+        assert!(close(&a.interpolate(&b, 1.0), &b));
+
+        // 同じ軸まわりの回転なので、中間は各成分の中間になる
+        let mid = Matrix3x4::from_scale(v3![1.5, 2.0, 2.0])
+            * Matrix3x4::from_rotate(Axis::Y, 0.8)
+            * Matrix3x4::from_translation(v3![2.0, 2.0, 3.0]);
+        assert!(close(&a.interpolate(&b, 0.5), &mid));
+    }
+
+    #[test]
+    fn interpolate_rotation_matches_slerp() {
+        let a = Matrix3x4::from_rotate_by(v3![0.0, 0.6, 0.8], 0.4);
+        let b = Matrix3x4::from_rotate_by(v3![1.0, -2.0, 0.5].normalize(), 2.5);
+        let qa = Quaternion::from_matrix(&a);
+        let qb = Quaternion::from_matrix(&b);
+
+        for t in [0.0, 0.3, 0.5, 0.9] {
+            let expected = Matrix3x4::from_quaternion(qa.slerp(qb, t));
+            assert!(close(&a.interpolate(&b, t), &expected));
+        }
+    }
+
+    #[test]
+    fn polar_decompose_mirrored_and_scaled() {
+        let m = Matrix3x4::from_scale(v3![-2.0, 1.0, 0.5]) * Matrix3x4::from_rotate(Axis::X, 0.7);
+        let (s, r) = m.polar_decompose();
+        assert!(close(&(s * r), &m));
+        assert!(r.is_orthogonal(1e-9));
+        assert!((r.determinant() - 1.0).abs() < 1e-9);
+        // 鏡映は伸縮側が持つ
+        assert!(s.has_mirroring());
+
+        assert!(close(&m.interpolate(&m, 0.3), &m));
+
+        let n = Matrix3x4::from_scale(v3![-1.0, 3.0, 0.5]) * Matrix3x4::from_rotate(Axis::X, 0.7);
+        let mid = Matrix3x4::from_scale(v3![-1.5, 2.0, 0.5]) * Matrix3x4::from_rotate(Axis::X, 0.7);
+        assert!(close(&m.interpolate(&n, 0.5), &mid));
+    }
+
+    #[test]
+    fn from_quaternion_matches_axis_angle() {
+        // m13 が q.y ではなく q.x を使っていた
+        let axis = v3![1.0, -2.0, 0.5].normalize();
+        let mut q = Quaternion::identitiy();
+        q.rotate_axis(axis, 1.1);
+        assert!(close(
+            &Matrix3x4::from_quaternion(q),
+            &Matrix3x4::from_rotate_by(axis, 1.1)
+        ));
+    }
+
+    #[test]
+    fn mul_uses_all_rows() {
+        // 2行目と3行目が右辺の誤った列を使っていた
+        let a = [
+            [1.0, 2.0, 3.0],
+            [4.0, 5.0, 6.0],
+            [7.0, 8.0, 10.0],
+            [1.0, -1.0, 2.0],
+        ];
+        let b = [
+            [2.0, 0.0, 1.0],
+            [-1.0, 3.0, 0.5],
+            [0.0, 4.0, -2.0],
+            [5.0, 6.0, 7.0],
+        ];
+        let m = Matrix3x4::from_array(a) * Matrix3x4::from_array(b);
+
+        let mut expected = [[0.0; 3]; 4];
+        for (i, row) in expected.iter_mut().enumerate() {
+            for (j, e) in row.iter_mut().enumerate() {
+                *e = (0..3).map(|k| a[i][k] * b[k][j]).sum::<f64>();
+                if i == 3 {
+                    *e += b[3][j];
+                }
+            }
+        }
+        assert_eq!(m.to_array(), expected);
+    }
+
+    #[test]
+    fn translation_accessors() {
+        let d = v3!(1.0, 2.0, 3.0);
+        assert_eq!(Matrix3x4::from_translation(d).get_translation(), d);
+
+        let orient = RotationMatrix::from_orientation(EulerAngles {
+            heading: 0.4,
+            pitch: -0.3,
+            bank: 1.1,
+        });
+        let to_parent = Matrix3x4::from_local_to_parent_matrix(d, orient);
+        let to_local = Matrix3x4::from_parent_to_local_matrix(d, orient);
+        assert!(to_parent
+            .get_position_from_local_to_parent_matrix()
+            .abs_diff_eq(&d, 1e-9));
+        assert!(to_local
+            .get_position_from_parent_to_local_matrix()
+            .abs_diff_eq(&d, 1e-9));
+    }
+}
@@ -0,0 +1,296 @@
+use std::ops::{Mul, MulAssign};
+
+use crate::{matrix::Matrix3x4, v2, vector::V3, vector2::V2, vector4::V4};
+
+// クリップ空間の深度の範囲
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthRange {
+    // OpenGL: near -> -1, far -> 1
+    NegativeOneToOne,
+    // Direct3D, Vulkan, wgpu: near -> 0, far -> 1
+    ZeroToOne,
+}
+
+// 4列目まで持つ4x4行列。Matrix3x4と同じく行ベクトルに右からかける (v * M)
+// 4列目 (m14, m24, m34, m44) は透視投影で使う。アフィン変換なら (0, 0, 0, 1)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix4x4 {
+    pub m11: f64,
+    pub m12: f64,
+    pub m13: f64,
+    pub m14: f64,
+    pub m21: f64,
+    pub m22: f64,
+    pub m23: f64,
+    pub m24: f64,
+    pub m31: f64,
+    pub m32: f64,
+    pub m33: f64,
+    pub m34: f64,
+    pub m41: f64,
+    pub m42: f64,
+    pub m43: f64,
+    pub m44: f64,
+}
+
+impl Matrix4x4 {
+    pub fn identity() -> Self {
+        Self::from_array([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // 透視投影。本と同じ左手系で +z が前方、fov_yは縦の視野角(ラジアン)
+    // 変換後のwにはカメラ空間のzが入り、perspective_divideで正規化デバイス座標になる
+    pub fn perspective(fov_y: f64, aspect: f64, near: f64, far: f64, depth: DepthRange) -> Self {
+        debug_assert!(near > 0.0 && far > near);
+
+        let zoom_y = 1.0 / (fov_y * 0.5).tan();
+        let zoom_x = zoom_y / aspect;
+        let (m33, m43) = match depth {
+            DepthRange::NegativeOneToOne => (
+                (far + near) / (far - near),
+                -2.0 * near * far / (far - near),
+            ),
+            DepthRange::ZeroToOne => (far / (far - near), -near * far / (far - near)),
+        };
+
+        Self::from_array([
+            [zoom_x, 0.0, 0.0, 0.0],
+            [0.0, zoom_y, 0.0, 0.0],
+            [0.0, 0.0, m33, 1.0],
+            [0.0, 0.0, m43, 0.0],
+        ])
+    }
+
+    // TAA用に、投影後の像を画面上でsampleピクセルだけずらす。viewportは画面の幅と高さ(ピクセル)
+    // sampleは正規化デバイス座標と同じ向き(xが右、yが上)。pixel_sampling::taa_jitterの値を渡す
+    // クリップ座標のx, yにwの定数倍を足すので、透視投影でも平行投影でも奥行きによらず同じだけずれる
+    pub fn jittered(&self, sample: V2, viewport: V2) -> Self {
+        let dx = 2.0 * sample.x / viewport.x;
+        let dy = 2.0 * sample.y / viewport.y;
+
+        let mut a = self.to_array();
+        for row in a.iter_mut() {
+            row[0] += dx * row[3];
+            row[1] += dy * row[3];
+        }
+
+        Self::from_array(a)
+    }
+
+    // jitteredで加えたずれをピクセル単位で取り出す。モーションベクトルからジッターを除くのに使う
+    // 中心が画面中央にある投影行列(perspectiveや平行投影)にだけ使える
+    pub fn jitter(&self, viewport: V2) -> V2 {
+        // 透視投影ならwはzの行から、平行投影なら平行移動の行から来る
+        let (x, y, w) = if self.m34 != 0.0 {
+            (self.m31, self.m32, self.m34)
+        } else {
+            (self.m41, self.m42, self.m44)
+        };
+
+        v2!(x / w * viewport.x * 0.5, y / w * viewport.y * 0.5)
+    }
+
+    // 行優先の配列
+    pub fn to_array(&self) -> [[f64; 4]; 4] {
+        [
+            [self.m11, self.m12, self.m13, self.m14],
+            [self.m21, self.m22, self.m23, self.m24],
+            [self.m31, self.m32, self.m33, self.m34],
+            [self.m41, self.m42, self.m43, self.m44],
+        ]
+    }
+
+    pub fn from_array(a: [[f64; 4]; 4]) -> Self {
+        Self {
+            m11: a[0][0],
+            m12: a[0][1],
+            m13: a[0][2],
+            m14: a[0][3],
+            m21: a[1][0],
+            m22: a[1][1],
+            m23: a[1][2],
+            m24: a[1][3],
+            m31: a[2][0],
+            m32: a[2][1],
+            m33: a[2][2],
+            m34: a[2][3],
+            m41: a[3][0],
+            m42: a[3][1],
+            m43: a[3][2],
+            m44: a[3][3],
+        }
+    }
+
+    pub fn transpose(&self) -> Self {
+        let a = self.to_array();
+        Self::from_array([0, 1, 2, 3].map(|i| [0, 1, 2, 3].map(|j| a[j][i])))
+    }
+
+    // 4列目が (0, 0, 0, 1) ならアフィン変換
+    pub fn is_affine(&self, epsilon: f64) -> bool {
+        self.m14.abs() <= epsilon
+            && self.m24.abs() <= epsilon
+            && self.m34.abs() <= epsilon
+            && (self.m44 - 1.0).abs() <= epsilon
+    }
+
+    // アフィン変換でなければ3x4では表せないのでNone
+    pub fn to_matrix3x4(&self) -> Option<Matrix3x4> {
+        if !self.is_affine(1e-12) {
+            return None;
+        }
+
+        let a = self.to_array();
+        Some(Matrix3x4::from_array(
+            [0, 1, 2, 3].map(|i| [a[i][0], a[i][1], a[i][2]]),
+        ))
+    }
+
+    // 点として変換してwで割る
+    pub fn transform_point(&self, p: V3) -> V3 {
+        (V4::from_point(p) * *self).perspective_divide()
+    }
+
+    // 方向として変換する。平行移動と4列目は効かない
+    pub fn transform_vector(&self, d: V3) -> V3 {
+        (V4::from_direction(d) * *self).xyz()
+    }
+}
+
+impl From<Matrix3x4> for Matrix4x4 {
+    fn from(m: Matrix3x4) -> Self {
+        let a = m.to_array();
+        let w = [0.0, 0.0, 0.0, 1.0];
+
+        Self::from_array([0, 1, 2, 3].map(|i| [a[i][0], a[i][1], a[i][2], w[i]]))
+    }
+}
+
+impl Mul<Matrix4x4> for V4 {
+    type Output = V4;
+
+    fn mul(self, rhs: Matrix4x4) -> Self::Output {
+        V4::new(
+            self.x * rhs.m11 + self.y * rhs.m21 + self.z * rhs.m31 + self.w * rhs.m41,
+            self.x * rhs.m12 + self.y * rhs.m22 + self.z * rhs.m32 + self.w * rhs.m42,
+            self.x * rhs.m13 + self.y * rhs.m23 + self.z * rhs.m33 + self.w * rhs.m43,
+            self.x * rhs.m14 + self.y * rhs.m24 + self.z * rhs.m34 + self.w * rhs.m44,
+        )
+    }
+}
+
+impl Mul<Matrix4x4> for Matrix4x4 {
+    type Output = Matrix4x4;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let a = self.to_array();
+        let b = rhs.to_array();
+
+        Self::from_array(
+            [0, 1, 2, 3].map(|i| [0, 1, 2, 3].map(|j| (0..4).map(|k| a[i][k] * b[k][j]).sum())),
+        )
+    }
+}
+
+impl MulAssign<Matrix4x4> for Matrix4x4 {
+    fn mul_assign(&mut self, rhs: Matrix4x4) {
+        *self = *self * rhs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v3;
+
+    fn sample() -> Matrix3x4 {
+        Matrix3x4::builder()
+            .scale(v3!(2.0, 1.0, 0.5))
+            .rotate_axis(v3!(0.0, 1.0, 0.0), 0.7)
+            .translate(v3!(1.0, -2.0, 3.0))
+            .build()
+    }
+
+    #[test]
+    fn agrees_with_matrix3x4() {
+        let m = sample();
+        let m4 = Matrix4x4::from(m);
+        let p = v3!(0.5, 1.5, -2.0);
+
+        assert!(m4.is_affine(0.0));
+        assert!((m4.transform_point(p) - p * m).mag() < 1e-12);
+        assert_eq!(m4.to_matrix3x4().unwrap().to_array(), m.to_array());
+
+        let product = Matrix4x4::from(m * m);
+        let product4 = m4 * m4;
+        for (a, b) in product.to_array().iter().zip(product4.to_array()) {
+            for (x, y) in a.iter().zip(b) {
+                assert!((x - y).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn perspective() {
+        let fov_y = std::f64::consts::FRAC_PI_2;
+        let gl = Matrix4x4::perspective(fov_y, 2.0, 1.0, 100.0, DepthRange::NegativeOneToOne);
+        let d3d = Matrix4x4::perspective(fov_y, 2.0, 1.0, 100.0, DepthRange::ZeroToOne);
+
+        assert!((gl.transform_point(v3!(0.0, 0.0, 1.0)).z + 1.0).abs() < 1e-12);
+        assert!((gl.transform_point(v3!(0.0, 0.0, 100.0)).z - 1.0).abs() < 1e-12);
+        assert!(d3d.transform_point(v3!(0.0, 0.0, 1.0)).z.abs() < 1e-12);
+        assert!((d3d.transform_point(v3!(0.0, 0.0, 100.0)).z - 1.0).abs() < 1e-12);
+
+        // 視野角90度なので、距離と同じ高さが画面の上端。横はアスペクト比の分だけ広い
+        let corner = d3d.transform_point(v3!(20.0, 10.0, 10.0));
+        assert!((corner.x - 1.0).abs() < 1e-12 && (corner.y - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn jittered_projection() {
+        let viewport = v2!(1920.0, 1080.0);
+        let sample = v2!(0.25, -0.375);
+        let projection = Matrix4x4::perspective(1.0, 16.0 / 9.0, 0.1, 100.0, DepthRange::ZeroToOne);
+        let jittered = projection.jittered(sample, viewport);
+
+        // 奥行きによらず、画面上で同じピクセル数だけずれる
+        for p in [v3!(1.0, 0.5, 2.0), v3!(-3.0, 2.0, 40.0)] {
+            let a = projection.transform_point(p);
+            let b = jittered.transform_point(p);
+            assert!(((b.x - a.x) * viewport.x * 0.5 - sample.x).abs() < 1e-9);
+            assert!(((b.y - a.y) * viewport.y * 0.5 - sample.y).abs() < 1e-9);
+            assert!((b.z - a.z).abs() < 1e-12);
+        }
+
+        assert_eq!(projection.jitter(viewport), v2!(0.0, 0.0));
+        let j = jittered.jitter(viewport);
+        assert!((j - sample).mag() < 1e-12);
+
+        // 平行投影でも取り出せる
+        let ortho = Matrix4x4::from(Matrix3x4::from_scale(v3!(0.1, 0.2, 0.01)));
+        let j = ortho.jittered(sample, viewport).jitter(viewport);
+        assert!((j - sample).mag() < 1e-12);
+    }
+
+    #[test]
+    fn projective_column() {
+        // z をそのまま w に写す単純な透視
+        let mut m = Matrix4x4::identity();
+        m.m34 = 1.0;
+        m.m44 = 0.0;
+
+        assert!(!m.is_affine(1e-9));
+        assert!(m.to_matrix3x4().is_none());
+        assert_eq!(m.transform_point(v3!(2.0, 4.0, 2.0)), v3!(1.0, 2.0, 1.0));
+        assert_eq!(m.transpose().m43, 1.0);
+        assert_eq!(
+            V4::new(1.0, 2.0, 3.0, 1.0) * Matrix4x4::identity(),
+            V4::new(1.0, 2.0, 3.0, 1.0)
+        );
+    }
+}
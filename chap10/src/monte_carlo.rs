@@ -0,0 +1,116 @@
+// サンプルを逐次加えて平均と分散を求める(Welfordの方法)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Estimator {
+    count: usize,
+    mean: f64,
+    // 平均からの偏差の二乗和
+    m2: f64,
+}
+
+impl Estimator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, sample: f64) {
+        self.count += 1;
+        let delta = sample - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (sample - self.mean);
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    // 不偏分散
+    pub fn variance(&self) -> f64 {
+        if self.count < 2 {
+            return 0.0;
+        }
+
+        self.m2 / (self.count - 1) as f64
+    }
+
+    // 平均の標準誤差
+    pub fn standard_error(&self) -> f64 {
+        if self.count == 0 {
+            return f64::INFINITY;
+        }
+
+        (self.variance() / self.count as f64).sqrt()
+    }
+
+    // 標準誤差 / |平均|
+    pub fn relative_error(&self) -> f64 {
+        if self.mean == 0.0 {
+            return if self.standard_error() == 0.0 {
+                0.0
+            } else {
+                f64::INFINITY
+            };
+        }
+
+        self.standard_error() / self.mean.abs()
+    }
+
+    // min_samples以上集めて相対誤差がtolerance以下になったら打ち切ってよい
+    pub fn converged(&self, tolerance: f64, min_samples: usize) -> bool {
+        self.count >= min_samples.max(2) && self.relative_error() <= tolerance
+    }
+}
+
+// 多重重点的サンプリングの重み。nf, ngは各手法のサンプル数、pdf_f, pdf_gは確率密度
+pub fn balance_heuristic(nf: usize, pdf_f: f64, ng: usize, pdf_g: f64) -> f64 {
+    let f = nf as f64 * pdf_f;
+    let g = ng as f64 * pdf_g;
+    if f + g == 0.0 {
+        return 0.0;
+    }
+
+    f / (f + g)
+}
+
+// 指数2のべき乗ヒューリスティック
+pub fn power_heuristic(nf: usize, pdf_f: f64, ng: usize, pdf_g: f64) -> f64 {
+    let f = nf as f64 * pdf_f;
+    let g = ng as f64 * pdf_g;
+    if f == 0.0 && g == 0.0 {
+        return 0.0;
+    }
+
+    (f * f) / (f * f + g * g)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn running_statistics() {
+        let mut estimator = Estimator::new();
+        for s in [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0] {
+            estimator.add(s);
+        }
+        assert_eq!(estimator.count(), 8);
+        assert_eq!(estimator.mean(), 5.0);
+        assert!((estimator.variance() - 32.0 / 7.0).abs() < 1e-12);
+        assert!(!estimator.converged(0.01, 2));
+        assert!(estimator.converged(0.5, 2));
+    }
+
+    #[test]
+    fn heuristics_sum_to_one() {
+        let a = balance_heuristic(1, 0.3, 2, 0.7);
+        let b = balance_heuristic(2, 0.7, 1, 0.3);
+        assert!((a + b - 1.0).abs() < 1e-12);
+
+        let a = power_heuristic(1, 0.3, 1, 0.7);
+        let b = power_heuristic(1, 0.7, 1, 0.3);
+        assert!((a + b - 1.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,260 @@
+use crate::vector::V3;
+
+// 常微分方程式の状態として使える型。線形結合と誤差の大きさだけを要求する
+pub trait OdeState: Copy {
+    // self + other * k
+    fn add_scaled(&self, other: &Self, k: f64) -> Self;
+
+    // 誤差の評価に使う大きさ。成分の絶対値の最大
+    fn error_norm(&self) -> f64;
+}
+
+impl OdeState for f64 {
+    fn add_scaled(&self, other: &Self, k: f64) -> Self {
+        self + other * k
+    }
+
+    fn error_norm(&self) -> f64 {
+        self.abs()
+    }
+}
+
+impl OdeState for V3 {
+    fn add_scaled(&self, other: &Self, k: f64) -> Self {
+        *self + *other * k
+    }
+
+    fn error_norm(&self) -> f64 {
+        self.abs().max_element()
+    }
+}
+
+impl<const N: usize> OdeState for [f64; N] {
+    fn add_scaled(&self, other: &Self, k: f64) -> Self {
+        let mut result = *self;
+        for (r, o) in result.iter_mut().zip(other) {
+            *r += o * k;
+        }
+
+        result
+    }
+
+    fn error_norm(&self) -> f64 {
+        self.iter().fold(0.0, |m, c| m.max(c.abs()))
+    }
+}
+
+// 位置と速度の組などに使う
+impl<A: OdeState, B: OdeState> OdeState for (A, B) {
+    fn add_scaled(&self, other: &Self, k: f64) -> Self {
+        (
+            self.0.add_scaled(&other.0, k),
+            self.1.add_scaled(&other.1, k),
+        )
+    }
+
+    fn error_norm(&self) -> f64 {
+        self.0.error_norm().max(self.1.error_norm())
+    }
+}
+
+// 採用したステップの時刻、状態、微分
+#[derive(Debug, Clone, Copy)]
+pub struct OdeSample<S> {
+    pub t: f64,
+    pub y: S,
+    pub dy: S,
+}
+
+// 積分結果。ステップの間はエルミート補間で任意の時刻の値を返す(密出力)
+#[derive(Debug, Clone)]
+pub struct OdeSolution<S> {
+    pub samples: Vec<OdeSample<S>>,
+}
+
+impl<S: OdeState> OdeSolution<S> {
+    pub fn last(&self) -> S {
+        self.samples[self.samples.len() - 1].y
+    }
+
+    // 範囲外の時刻は端の値にクランプする
+    pub fn sample(&self, t: f64) -> S {
+        let first = &self.samples[0];
+        let last = &self.samples[self.samples.len() - 1];
+        if t <= first.t {
+            return first.y;
+        }
+        if t >= last.t {
+            return last.y;
+        }
+
+        let i = self.samples.partition_point(|s| s.t <= t) - 1;
+        let (a, b) = (&self.samples[i], &self.samples[i + 1]);
+        let h = b.t - a.t;
+        let s = (t - a.t) / h;
+        let s2 = s * s;
+        let s3 = s2 * s;
+
+        let h00 = 2.0 * s3 - 3.0 * s2 + 1.0;
+        let h10 = s3 - 2.0 * s2 + s;
+        let h01 = -2.0 * s3 + 3.0 * s2;
+        let h11 = s3 - s2;
+
+        a.y.add_scaled(&a.y, h00 - 1.0)
+            .add_scaled(&a.dy, h10 * h)
+            .add_scaled(&b.y, h01)
+            .add_scaled(&b.dy, h11 * h)
+    }
+}
+
+// ルンゲ=クッタ=フェールベルグ法 (RKF45)。4次と5次の解の差から誤差を見積もり、刻み幅を調整する
+#[derive(Debug, Clone, Copy)]
+pub struct Rkf45 {
+    // 1ステップあたりの許容誤差(絶対誤差)
+    pub tolerance: f64,
+    // これより小さい刻みでは誤差が大きくても先に進める
+    pub min_step: f64,
+    pub max_step: f64,
+}
+
+impl Rkf45 {
+    pub fn new(tolerance: f64) -> Self {
+        Self {
+            tolerance,
+            min_step: 1e-9,
+            max_step: f64::INFINITY,
+        }
+    }
+
+    // 1ステップ。5次の解と誤差の見積もりを返す
+    fn step<S, F>(f: &F, t: f64, y: &S, k1: &S, h: f64) -> (S, S)
+    where
+        S: OdeState,
+        F: Fn(f64, &S) -> S,
+    {
+        let comb = |terms: &[(&S, f64)]| {
+            terms
+                .iter()
+                .fold(*y, |acc, (k, c)| acc.add_scaled(k, c * h))
+        };
+
+        let k2 = f(t + h / 4.0, &comb(&[(k1, 1.0 / 4.0)]));
+        let k3 = f(
+            t + 3.0 * h / 8.0,
+            &comb(&[(k1, 3.0 / 32.0), (&k2, 9.0 / 32.0)]),
+        );
+        let k4 = f(
+            t + 12.0 * h / 13.0,
+            &comb(&[
+                (k1, 1932.0 / 2197.0),
+                (&k2, -7200.0 / 2197.0),
+                (&k3, 7296.0 / 2197.0),
+            ]),
+        );
+        let k5 = f(
+            t + h,
+            &comb(&[
+                (k1, 439.0 / 216.0),
+                (&k2, -8.0),
+                (&k3, 3680.0 / 513.0),
+                (&k4, -845.0 / 4104.0),
+            ]),
+        );
+        let k6 = f(
+            t + h / 2.0,
+            &comb(&[
+                (k1, -8.0 / 27.0),
+                (&k2, 2.0),
+                (&k3, -3544.0 / 2565.0),
+                (&k4, 1859.0 / 4104.0),
+                (&k5, -11.0 / 40.0),
+            ]),
+        );
+
+        let y5 = comb(&[
+            (k1, 16.0 / 135.0),
+            (&k3, 6656.0 / 12825.0),
+            (&k4, 28561.0 / 56430.0),
+            (&k5, -9.0 / 50.0),
+            (&k6, 2.0 / 55.0),
+        ]);
+        // 5次と4次の解の差
+        let zero = y.add_scaled(y, -1.0);
+        let error = [
+            (k1, 1.0 / 360.0),
+            (&k3, -128.0 / 4275.0),
+            (&k4, -2197.0 / 75240.0),
+            (&k5, 1.0 / 50.0),
+            (&k6, 2.0 / 55.0),
+        ]
+        .iter()
+        .fold(zero, |acc, (k, c)| acc.add_scaled(k, c * h));
+
+        (y5, error)
+    }
+
+    // y' = f(t, y) を t0 から t1 まで積分する。hは最初の刻み幅
+    pub fn integrate<S, F>(&self, f: F, t0: f64, y0: S, t1: f64, h: f64) -> OdeSolution<S>
+    where
+        S: OdeState,
+        F: Fn(f64, &S) -> S,
+    {
+        let mut t = t0;
+        let mut y = y0;
+        let mut dy = f(t, &y);
+        let mut h = h.abs().clamp(self.min_step, self.max_step);
+        let mut samples = vec![OdeSample { t, y, dy }];
+
+        while t < t1 {
+            h = h.min(t1 - t);
+            let (next, error) = Self::step(&f, t, &y, &dy, h);
+            let error = error.error_norm();
+
+            if error <= self.tolerance || h <= self.min_step {
+                t = if t1 - t <= h { t1 } else { t + h };
+                y = next;
+                dy = f(t, &y);
+                samples.push(OdeSample { t, y, dy });
+            }
+
+            // 誤差は h^5 に比例するので、許容誤差に収まる刻みを見積もる
+            let factor = if error == 0.0 {
+                4.0
+            } else {
+                (0.9 * (self.tolerance / error).powf(0.2)).clamp(0.1, 4.0)
+            };
+            h = (h * factor).clamp(self.min_step, self.max_step);
+        }
+
+        OdeSolution { samples }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exponential_decay() {
+        let solution = Rkf45::new(1e-10).integrate(|_, y: &f64| -y, 0.0, 1.0, 2.0, 0.1);
+
+        assert!((solution.last() - (-2.0f64).exp()).abs() < 1e-8);
+        // 密出力もステップの間で精度を保つ
+        for t in [0.05f64, 0.33, 1.7] {
+            assert!((solution.sample(t) - (-t).exp()).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn harmonic_oscillator() {
+        // x'' = -x を (位置, 速度) の組で解く
+        let f = |_: f64, (x, v): &(f64, f64)| (*v, -x);
+        let precise = Rkf45::new(1e-10).integrate(f, 0.0, (1.0, 0.0), 10.0, 0.01);
+        let rough = Rkf45::new(1e-4).integrate(f, 0.0, (1.0, 0.0), 10.0, 0.01);
+
+        assert!((precise.last().0 - 10.0f64.cos()).abs() < 1e-7);
+        assert!((precise.last().1 + 10.0f64.sin()).abs() < 1e-7);
+        assert!(rough.samples.len() < precise.samples.len());
+        assert_eq!(precise.samples.last().unwrap().t, 10.0);
+    }
+}
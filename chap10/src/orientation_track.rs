@@ -0,0 +1,328 @@
+use crate::{quaternion::Quaternion, utils::GameMath, v3, vector::V3};
+
+// 時刻timeでの向き
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrientationKey {
+    pub time: f64,
+    pub rotation: Quaternion,
+}
+
+impl OrientationKey {
+    pub fn new(time: f64, rotation: Quaternion) -> Self {
+        Self { time, rotation }
+    }
+}
+
+// 四元数のキーをsquadで滑らかにつなぐ
+// キーごとに前後の区間用の内側の制御点を持ち、キーの間隔が不均一でもキーの上で角速度が連続になる
+pub struct OrientationTrack {
+    keys: Vec<OrientationKey>,
+    // (前の区間の終わり側, 次の区間の始め側)
+    inner: Vec<(Quaternion, Quaternion)>,
+}
+
+// キー削減の結果。誤差は確かめた時刻での元のトラックとの角度の差の最大値(ラジアン)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reduction {
+    pub original_keys: usize,
+    pub kept_keys: usize,
+    pub max_error: f64,
+}
+
+impl Reduction {
+    // 元のキー数 / 残したキー数
+    pub fn compression_ratio(&self) -> f64 {
+        self.original_keys as f64 / self.kept_keys as f64
+    }
+}
+
+// 2つの向きのなす角。qと-qは同じ向き
+// slerpは角度が小さいと線形補間になり長さが1からわずかにずれるので、長さで割ってから比べる
+fn angle_between(a: Quaternion, b: Quaternion) -> f64 {
+    let cos = a.dot(b).abs() / (a.dot(a) * b.dot(b)).sqrt();
+    2.0 * cos.safe_acos()
+}
+
+fn negate(q: Quaternion) -> Quaternion {
+    Quaternion {
+        w: -q.w,
+        x: -q.x,
+        y: -q.y,
+        z: -q.z,
+    }
+}
+
+// q * exp(v)。vは純虚四元数の虚部
+fn offset(q: Quaternion, v: V3) -> Quaternion {
+    q * Quaternion {
+        w: 0.0,
+        x: v.x,
+        y: v.y,
+        z: v.z,
+    }
+    .exp()
+}
+
+// log(q^-1 * other) の虚部
+fn log_between(q: Quaternion, other: Quaternion) -> V3 {
+    let l = (q.conjugate() * other).log();
+    v3!(l.x, l.y, l.z)
+}
+
+// キーiの前後の内側の制御点
+// 一般的なsquadの s_i = q_i * exp(-(log(q_i^-1 * q_{i+1}) + log(q_i^-1 * q_{i-1})) / 4) を、
+// 区間の長さで接線を配分して時間あたりの変化率が揃うように拡張したもの
+fn inner_points(
+    prev: &OrientationKey,
+    key: &OrientationKey,
+    next: &OrientationKey,
+) -> (Quaternion, Quaternion) {
+    let a = log_between(key.rotation, next.rotation);
+    let b = log_between(key.rotation, prev.rotation);
+    let dt_prev = key.time - prev.time;
+    let dt_next = next.time - key.time;
+
+    // キーでの接線(時間あたり)。前後の差分を全体の時間で割る
+    let tangent = (a - b) / (dt_prev + dt_next);
+
+    (
+        offset(key.rotation, (tangent * dt_prev + b) * -0.5),
+        offset(key.rotation, (tangent * dt_next - a) * 0.5),
+    )
+}
+
+impl OrientationTrack {
+    // キーは時刻の昇順に並べておく
+    pub fn new(mut keys: Vec<OrientationKey>) -> Self {
+        assert!(!keys.is_empty());
+        assert!(keys.windows(2).all(|w| w[0].time < w[1].time));
+
+        // qと-qは同じ向きなので、隣り合うキーが短い方の弧でつながるよう符号を揃える
+        for i in 1..keys.len() {
+            if keys[i - 1].rotation.dot(keys[i].rotation) < 0.0 {
+                keys[i].rotation = negate(keys[i].rotation);
+            }
+        }
+
+        let last = keys.len() - 1;
+        let inner = (0..keys.len())
+            .map(|i| {
+                if i == 0 || i == last {
+                    return (keys[i].rotation, keys[i].rotation);
+                }
+                inner_points(&keys[i - 1], &keys[i], &keys[i + 1])
+            })
+            .collect();
+
+        Self { keys, inner }
+    }
+
+    pub fn keys(&self) -> &[OrientationKey] {
+        &self.keys
+    }
+
+    pub fn start_time(&self) -> f64 {
+        self.keys[0].time
+    }
+
+    pub fn end_time(&self) -> f64 {
+        self.keys[self.keys.len() - 1].time
+    }
+
+    // tを含む区間の先頭のキーと、区間内の位置 [0, 1]
+    fn segment(&self, t: f64) -> (usize, f64) {
+        let last = self.keys.len() - 1;
+        if last == 0 || t <= self.start_time() {
+            return (0, 0.0);
+        }
+        if t >= self.end_time() {
+            return (last - 1, 1.0);
+        }
+
+        let i = self.keys.partition_point(|k| k.time <= t) - 1;
+        let (t0, t1) = (self.keys[i].time, self.keys[i + 1].time);
+        (i, (t - t0) / (t1 - t0))
+    }
+
+    // 範囲外の時刻は端のキーの向き
+    pub fn sample(&self, t: f64) -> Quaternion {
+        if self.keys.len() == 1 {
+            return self.keys[0].rotation;
+        }
+
+        let (i, h) = self.segment(t);
+        let (q0, q1) = (self.keys[i].rotation, self.keys[i + 1].rotation);
+        let (s0, s1) = (self.inner[i].1, self.inner[i + 1].0);
+
+        q0.slerp(q1, h).slerp(s0.slerp(s1, h), 2.0 * h * (1.0 - h))
+    }
+
+    // 時刻tでの角速度(回転軸 * 角速度)を中心差分で求める
+    pub fn angular_velocity(&self, t: f64) -> V3 {
+        if self.keys.len() == 1 {
+            return v3!(0.0, 0.0, 0.0);
+        }
+
+        let (i, _) = self.segment(t);
+        let step = (self.keys[i + 1].time - self.keys[i].time) * 1e-5;
+        let t0 = (t - step).max(self.start_time());
+        let t1 = (t + step).min(self.end_time());
+
+        Quaternion::angular_velocity_between(&self.sample(t0), &self.sample(t1), t1 - t0)
+    }
+
+    // 補間で誤差tolerance(ラジアン)以内に再現できるキーを取り除く
+    // 両端のキーから始め、元のトラックとの誤差が最も大きい時刻に近いキーを足していく
+    // 誤差は元のキーの時刻と、隣り合うキーの中間の時刻で測る
+    pub fn reduce(&self, tolerance: f64) -> (OrientationTrack, Reduction) {
+        let mut times: Vec<f64> = self
+            .keys
+            .windows(2)
+            .flat_map(|w| [w[0].time, 0.5 * (w[0].time + w[1].time)])
+            .collect();
+        times.push(self.end_time());
+        let expected: Vec<Quaternion> = times.iter().map(|&t| self.sample(t)).collect();
+
+        let mut kept = vec![false; self.keys.len()];
+        kept[0] = true;
+        kept[self.keys.len() - 1] = true;
+
+        loop {
+            let keys = self
+                .keys
+                .iter()
+                .zip(&kept)
+                .filter(|(_, &k)| k)
+                .map(|(key, _)| *key)
+                .collect();
+            let track = OrientationTrack::new(keys);
+
+            let (worst, max_error) = times
+                .iter()
+                .zip(&expected)
+                .map(|(&t, &q)| (t, angle_between(track.sample(t), q)))
+                .fold((0.0, 0.0), |a, b| if b.1 > a.1 { b } else { a });
+
+            if max_error <= tolerance || kept.iter().all(|&k| k) {
+                let reduction = Reduction {
+                    original_keys: self.keys.len(),
+                    kept_keys: track.keys.len(),
+                    max_error,
+                };
+                return (track, reduction);
+            }
+
+            // まだ使っていないキーのうち、誤差が最大の時刻に最も近いもの
+            let next = (0..self.keys.len())
+                .filter(|&i| !kept[i])
+                .min_by(|&a, &b| {
+                    let da = (self.keys[a].time - worst).abs();
+                    let db = (self.keys[b].time - worst).abs();
+                    da.total_cmp(&db)
+                })
+                .unwrap();
+            kept[next] = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rotated(axis: V3, theta: f64) -> Quaternion {
+        let mut q = Quaternion::identitiy();
+        q.rotate_axis(axis.normalize(), theta);
+        q
+    }
+
+    fn track() -> OrientationTrack {
+        OrientationTrack::new(vec![
+            OrientationKey::new(0.0, Quaternion::identitiy()),
+            OrientationKey::new(1.0, rotated(v3!(0.0, 1.0, 0.0), 0.8)),
+            OrientationKey::new(1.5, rotated(v3!(1.0, 1.0, 0.0), 1.4)),
+            OrientationKey::new(3.0, rotated(v3!(0.0, 0.0, 1.0), -0.5)),
+        ])
+    }
+
+    #[test]
+    fn passes_through_keys() {
+        let track = track();
+        for key in track.keys() {
+            assert!(track
+                .sample(key.time)
+                .approx_eq_rotation(&key.rotation, 1e-12));
+        }
+        assert!(track
+            .sample(-1.0)
+            .approx_eq_rotation(&Quaternion::identitiy(), 1e-12));
+        assert_eq!(track.sample(10.0), track.keys()[3].rotation);
+    }
+
+    #[test]
+    fn angular_velocity_continuous_at_keys() {
+        let track = track();
+        for key in &track.keys()[1..3] {
+            let before = track.angular_velocity(key.time - 1e-4);
+            let after = track.angular_velocity(key.time + 1e-4);
+            assert!((before - after).mag() < 5e-3, "{:?} {:?}", before, after);
+        }
+    }
+
+    #[test]
+    fn reduce_keys() {
+        // 一定の角速度の回転は両端のキーだけで再現できる
+        let axis = v3!(0.0, 0.6, 0.8);
+        let keys = (0..21)
+            .map(|i| OrientationKey::new(i as f64 * 0.1, rotated(axis, 1.3 * i as f64 * 0.1)))
+            .collect();
+        let (reduced, reduction) = OrientationTrack::new(keys).reduce(1e-6);
+        assert_eq!(reduced.keys().len(), 2);
+        assert_eq!(reduction.compression_ratio(), 10.5);
+
+        // 滑らかな動きを細かくサンプリングしたキーは、誤差の範囲で間引ける
+        let dense = OrientationTrack::new(
+            (0..=60)
+                .map(|i| {
+                    let t = i as f64 * 0.05;
+                    OrientationKey::new(t, rotated(v3!(t.cos(), 1.0, 0.2 * t), t.sin()))
+                })
+                .collect(),
+        );
+        let tolerance = 1e-2;
+        let (reduced, reduction) = dense.reduce(tolerance);
+        assert!(reduction.compression_ratio() > 4.0);
+        assert!(reduction.max_error <= tolerance);
+        for key in dense.keys() {
+            assert!(angle_between(reduced.sample(key.time), key.rotation) <= tolerance);
+        }
+
+        // 許容誤差が0なら向きの変わるキーはすべて残る
+        let (reduced, reduction) = track().reduce(0.0);
+        assert_eq!(reduced.keys().len(), 4);
+        assert_eq!(reduction.compression_ratio(), 1.0);
+    }
+
+    #[test]
+    fn uniform_rotation() {
+        // 一定の角速度で回るキーからは同じ角速度が得られる
+        let axis = v3!(0.0, 0.6, 0.8);
+        let omega = 1.3;
+        let keys = (0..5)
+            .map(|i| {
+                let t = i as f64 * 0.5;
+                // 符号が交互でも同じ向き
+                let q = rotated(axis, omega * t);
+                OrientationKey::new(t, if i % 2 == 0 { q } else { negate(q) })
+            })
+            .collect();
+        let track = OrientationTrack::new(keys);
+
+        for t in [0.1, 0.5, 0.8, 1.7] {
+            assert!(track
+                .sample(t)
+                .approx_eq_rotation(&rotated(axis, omega * t), 1e-9));
+            assert!((track.angular_velocity(t) - axis * omega).mag() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{v2, vector2::V2};
+
+// 基数baseでの逆順の小数。Halton列の各次元になる
+pub fn radical_inverse(base: u32, mut index: u32) -> f64 {
+    let inv_base = 1.0 / base as f64;
+    let mut f = inv_base;
+    let mut result = 0.0;
+
+    while index > 0 {
+        result += f * (index % base) as f64;
+        index /= base;
+        f *= inv_base;
+    }
+
+    result
+}
+
+// 基数2, 3のHalton列
+pub fn halton_2d(index: u32) -> (f64, f64) {
+    (radical_inverse(2, index), radical_inverse(3, index))
+}
+
+// TAAでframe番目に使うジッター。ピクセル中心からのずれ [-0.5, 0.5) をピクセル単位で返す
+// Halton列をsequence_lengthフレームごとに繰り返す。短い周期(8や16)の方が履歴が安定する
+pub fn taa_jitter(frame: u32, sequence_length: u32) -> V2 {
+    assert!(sequence_length > 0);
+
+    let (u, v) = halton_2d(frame % sequence_length + 1);
+    v2!(u - 0.5, v - 0.5)
+}
+
+// 整数のハッシュ(PCG)。同じ入力からは常に同じ値になる
+fn hash(v: u32) -> u32 {
+    let state = v.wrapping_mul(747796405).wrapping_add(2891336453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+    (word >> 22) ^ word
+}
+
+fn to_unit(v: u32) -> f64 {
+    v as f64 / (u32::MAX as f64 + 1.0)
+}
+
+// ピクセル(x, y)内のサンプル位置をピクセル座標で返す
+// ピクセルを層に分けて各層でずらし、フレームごとにHalton列で全体を巡回シフトする
+pub fn pixel_samples(x: u32, y: u32, frame: u32, samples_per_pixel: usize) -> Vec<(f64, f64)> {
+    if samples_per_pixel == 0 {
+        return Vec::new();
+    }
+
+    let columns = (samples_per_pixel as f64).sqrt().ceil() as usize;
+    let rows = samples_per_pixel.div_ceil(columns);
+    // 0番目は(0, 0)なので1から使う。u32::MAXの次は0に戻る
+    let (shift_u, shift_v) = halton_2d(frame.wrapping_add(1));
+    let seed = hash(x ^ hash(y ^ hash(frame)));
+
+    (0..samples_per_pixel)
+        .map(|i| {
+            let h = hash(seed ^ i as u32);
+            let jitter_u = to_unit(h);
+            let jitter_v = to_unit(hash(h));
+
+            let u = ((i % columns) as f64 + jitter_u) / columns as f64;
+            let v = ((i / columns) as f64 + jitter_v) / rows as f64;
+
+            (
+                x as f64 + (u + shift_u).fract(),
+                y as f64 + (v + shift_v).fract(),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn halton_sequence() {
+        assert_eq!(halton_2d(1), (0.5, 1.0 / 3.0));
+        assert_eq!(halton_2d(2), (0.25, 2.0 / 3.0));
+        assert_eq!(radical_inverse(2, 3), 0.75);
+    }
+
+    #[test]
+    fn samples_are_deterministic_and_inside_pixel() {
+        let a = pixel_samples(12, 7, 3, 8);
+        assert_eq!(a, pixel_samples(12, 7, 3, 8));
+        assert_ne!(a, pixel_samples(12, 7, 4, 8));
+        assert_eq!(a.len(), 8);
+
+        for (u, v) in a {
+            assert!((12.0..13.0).contains(&u));
+            assert!((7.0..8.0).contains(&v));
+        }
+    }
+
+    #[test]
+    fn taa_jitter_cycles() {
+        assert_eq!(taa_jitter(0, 8), v2!(0.0, 1.0 / 3.0 - 0.5));
+        assert_eq!(taa_jitter(3, 8), taa_jitter(11, 8));
+        assert_ne!(taa_jitter(3, 8), taa_jitter(4, 8));
+
+        for frame in 0..16 {
+            let j = taa_jitter(frame, 16);
+            assert!((-0.5..0.5).contains(&j.x) && (-0.5..0.5).contains(&j.y));
+        }
+        assert_eq!(taa_jitter(u32::MAX, 16).x, taa_jitter(15, 16).x);
+    }
+
+    #[test]
+    fn last_frame_does_not_overflow() {
+        let a = pixel_samples(0, 0, u32::MAX, 4);
+        assert_eq!(a.len(), 4);
+        for (u, v) in a {
+            assert!((0.0..1.0).contains(&u) && (0.0..1.0).contains(&v));
+        }
+    }
+}
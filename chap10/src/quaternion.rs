@@ -1,6 +1,9 @@
 use std::ops::{Mul, MulAssign};
 
-use crate::{euler_angles::EulerAngles, utils::GameMath, v3, vector::V3};
+use crate::{
+    debug_assert_finite, euler_angles::EulerAngles, matrix::Matrix3x4, utils::GameMath, v3,
+    vector::V3,
+};
 
 const QUATERNION_IDENTITY: Quaternion = Quaternion {
     w: 1.0,
@@ -9,6 +12,7 @@ const QUATERNION_IDENTITY: Quaternion = Quaternion {
     z: 0.0,
 };
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Quaternion {
     pub w: f64,
     pub x: f64,
@@ -16,8 +20,63 @@ pub struct Quaternion {
     pub z: f64,
 }
 
+// 微小回転の近似の次数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmallAngleOrder {
+    // cos ≈ 1, sin ≈ α。誤差はおよそ α^2 / 2
+    First,
+    // cos ≈ 1 - α^2 / 2, sin ≈ α - α^3 / 6。誤差はおよそ α^4 / 24
+    Second,
+    // 三角関数をそのまま使う
+    Exact,
+}
+
+impl SmallAngleOrder {
+    // 半角alphaの2乗に対して誤差がtolerance以下になる最も安い近似。平方根は取らない
+    pub fn select(alpha_sq: f64, tolerance: f64) -> Self {
+        if alpha_sq * 0.5 <= tolerance {
+            SmallAngleOrder::First
+        } else if alpha_sq * alpha_sq / 24.0 <= tolerance {
+            SmallAngleOrder::Second
+        } else {
+            SmallAngleOrder::Exact
+        }
+    }
+}
+
+// カメラなどの上方向の決め方
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UpVector {
+    // ワールドの上方向に固定。地平線が傾かない
+    World(V3),
+    // 曲線のフレームの上方向(法線など)に追従する。ループでも破綻しない
+    Path(V3),
+    // worldからpathへblend [0, 1] で球面補間する
+    Blend { world: V3, path: V3, blend: f64 },
+}
+
+impl UpVector {
+    pub fn resolve(&self) -> V3 {
+        match self {
+            UpVector::World(up) | UpVector::Path(up) => up.normalize(),
+            UpVector::Blend { world, path, blend } => world
+                .normalize()
+                .slerp(&path.normalize(), blend.clamp(0.0, 1.0)),
+        }
+    }
+}
+
+// 回転を量子化したハッシュ可能なキー。qと-qは同じキーになる
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct QuaternionKey {
+    pub w: i64,
+    pub x: i64,
+    pub y: i64,
+    pub z: i64,
+}
+
 impl Quaternion {
-    fn identitiy() -> Self {
+    pub fn identitiy() -> Self {
         Quaternion {
             w: 1.0,
             x: 0.0,
@@ -26,7 +85,7 @@ impl Quaternion {
         }
     }
 
-    fn rotate_x(&mut self, theta: f64) {
+    pub fn rotate_x(&mut self, theta: f64) {
         let theta_over_2 = theta * 0.5;
         self.w = theta_over_2.cos();
         self.x = theta_over_2.sin();
@@ -34,7 +93,7 @@ impl Quaternion {
         self.z = 0.0;
     }
 
-    fn rotate_y(&mut self, theta: f64) {
+    pub fn rotate_y(&mut self, theta: f64) {
         let theta_over_2 = theta * 0.5;
         self.w = theta_over_2.cos();
         self.x = 0.0;
@@ -42,7 +101,7 @@ impl Quaternion {
         self.z = 0.0;
     }
 
-    fn rotate_z(&mut self, theta: f64) {
+    pub fn rotate_z(&mut self, theta: f64) {
         let theta_over_2 = theta * 0.5;
         self.w = theta_over_2.cos();
         self.x = 0.0;
@@ -50,7 +109,7 @@ impl Quaternion {
         self.z = theta_over_2.sin();
     }
 
-    fn rotate_axis(&mut self, axis: V3, theta: f64) {
+    pub fn rotate_axis(&mut self, axis: V3, theta: f64) {
         assert!(axis.mag().abs() - 1.0 < 0.01);
 
         let theta_over_2 = theta * 0.5;
@@ -62,7 +121,7 @@ impl Quaternion {
         self.z = axis.z * sin_theta_over_2;
     }
 
-    fn rotate_obj_to_inertial(&mut self, orientation: EulerAngles) {
+    pub fn rotate_obj_to_inertial(&mut self, orientation: EulerAngles) {
         let p = (orientation.pitch * 0.5).sin_cos();
         let b = (orientation.bank * 0.5).sin_cos();
         let h = (orientation.heading * 0.5).sin_cos();
@@ -73,7 +132,7 @@ impl Quaternion {
         self.z = -h.0 * p.0 * b.1 + h.1 * p.1 * b.0;
     }
 
-    fn rotate_inertial_to_obj(&mut self, orientation: EulerAngles) {
+    pub fn rotate_inertial_to_obj(&mut self, orientation: EulerAngles) {
         let p = (orientation.pitch * 0.5).sin_cos();
         let b = (orientation.bank * 0.5).sin_cos();
         let h = (orientation.heading * 0.5).sin_cos();
@@ -84,7 +143,7 @@ impl Quaternion {
         self.z = h.0 * p.0 * b.1 - h.1 * p.1 * b.0;
     }
 
-    fn normalize(&mut self) {
+    pub fn normalize(&mut self) {
         let mag = (self.w * self.w + self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
 
         if mag > 0.0 {
@@ -96,12 +155,47 @@ impl Quaternion {
         }
     }
 
-    fn get_rotation_angle(&self) -> f64 {
+    pub fn is_finite(&self) -> bool {
+        [self.w, self.x, self.y, self.z]
+            .iter()
+            .all(|c| c.is_finite())
+    }
+
+    pub fn is_nan(&self) -> bool {
+        [self.w, self.x, self.y, self.z].iter().any(|c| c.is_nan())
+    }
+
+    // 大きさが0(や小さすぎて逆数が無限大になる)ならNone
+    pub fn try_normalize(&self) -> Option<Quaternion> {
+        let mag = (self.w * self.w + self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
+        let one_over_mag = 1.0 / mag;
+        if !one_over_mag.is_finite() || one_over_mag <= 0.0 {
+            return None;
+        }
+
+        Some(Quaternion {
+            w: self.w * one_over_mag,
+            x: self.x * one_over_mag,
+            y: self.y * one_over_mag,
+            z: self.z * one_over_mag,
+        })
+    }
+
+    pub fn normalize_or(&self, fallback: Quaternion) -> Quaternion {
+        self.try_normalize().unwrap_or(fallback)
+    }
+
+    // 四元数のゼロは回転として意味がないので、単位元に倒す
+    pub fn normalize_or_identity(&self) -> Quaternion {
+        self.normalize_or(QUATERNION_IDENTITY)
+    }
+
+    pub fn get_rotation_angle(&self) -> f64 {
         let theta_over_2 = self.w.safe_acos();
         theta_over_2 * 2.0
     }
 
-    fn get_rotation_axis(&self) -> V3 {
+    pub fn get_rotation_axis(&self) -> V3 {
         let sin_theta_over_2_sq = 1.0 - self.w * self.w;
 
         if sin_theta_over_2_sq <= 0.0 {
@@ -117,11 +211,11 @@ impl Quaternion {
         ]
     }
 
-    fn dot(&self, other: Quaternion) -> f64 {
+    pub fn dot(&self, other: Quaternion) -> f64 {
         self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
     }
 
-    fn slerp(&self, other: Quaternion, t: f64) -> Quaternion {
+    pub fn slerp(&self, other: Quaternion, t: f64) -> Quaternion {
         if t <= 0.0 {
             return *self;
         }
@@ -132,10 +226,11 @@ impl Quaternion {
         // 内積から角度を求める
         let mut cos_omega = self.dot(other);
 
-        let mut w = self.w;
-        let mut x = self.x;
-        let mut y = self.y;
-        let mut z = self.z;
+        // 負の場合は片方を反転して短い方の弧を通る
+        let mut w = other.w;
+        let mut x = other.x;
+        let mut y = other.y;
+        let mut z = other.z;
         if cos_omega < 0.0 {
             w = -w;
             x = -x;
@@ -144,13 +239,9 @@ impl Quaternion {
             cos_omega = -cos_omega;
         }
 
-        let mut k0 = 0.0;
-        let mut k1 = 0.0;
-
         // omegaが0に近い場合、0除算防ぐために線形補完に切り替える
-        if cos_omega > 0.9999 {
-            k0 = 1.0 - t;
-            k1 = t;
+        let (k0, k1) = if cos_omega > 0.9999 {
+            (1.0 - t, t)
         } else {
             let sin_omega = (1.0 - cos_omega * cos_omega).sqrt();
 
@@ -158,20 +249,22 @@ impl Quaternion {
 
             let one_over_sign_omega = 1.0 / sin_omega;
 
-            k0 = (((1.0 - t) * omega) * one_over_sign_omega).sin();
-            k1 = ((t * omega) * one_over_sign_omega).sin();
-        }
+            (
+                ((1.0 - t) * omega).sin() * one_over_sign_omega,
+                (t * omega).sin() * one_over_sign_omega,
+            )
+        };
 
         Quaternion {
-            w: k0 * self.w + k1 * other.w,
-            x: k0 * self.x + k1 * other.x,
-            y: k0 * self.y + k1 * other.y,
-            z: k0 * self.z + k1 * other.z,
+            w: k0 * self.w + k1 * w,
+            x: k0 * self.x + k1 * x,
+            y: k0 * self.y + k1 * y,
+            z: k0 * self.z + k1 * z,
         }
     }
 
     // 共役
-    fn conjugate(&self, other: Quaternion) -> Quaternion {
+    pub fn conjugate(&self) -> Quaternion {
         Quaternion {
             w: self.w,
             x: -self.x,
@@ -180,7 +273,7 @@ impl Quaternion {
         }
     }
 
-    fn pow(&self, exp: f64) -> Quaternion {
+    pub fn pow(&self, exp: f64) -> Quaternion {
         if self.w.abs() > 0.9999 {
             return *self;
         }
@@ -196,6 +289,260 @@ impl Quaternion {
             z: self.z * mult,
         }
     }
+
+    // 対数 log(q) = [0, αn] (α = θ/2)。単位四元数を前提とする
+    pub fn log(&self) -> Quaternion {
+        let alpha = self.w.safe_acos();
+        let sin_alpha = alpha.sin();
+        // 回転がほぼ0のときは sin(α)/α → 1 とみなす
+        let k = if sin_alpha.abs() < 1e-9 {
+            1.0
+        } else {
+            alpha / sin_alpha
+        };
+
+        Quaternion {
+            w: 0.0,
+            x: self.x * k,
+            y: self.y * k,
+            z: self.z * k,
+        }
+    }
+
+    // 指数 exp([0, αn]) = [cos α, n sin α]。wは無視する
+    pub fn exp(&self) -> Quaternion {
+        let alpha = (self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
+        let k = if alpha < 1e-9 {
+            1.0
+        } else {
+            alpha.sin() / alpha
+        };
+
+        Quaternion {
+            w: alpha.cos(),
+            x: self.x * k,
+            y: self.y * k,
+            z: self.z * k,
+        }
+    }
+
+    // ベクトルを回転させる。この積の順序では q^-1 * p * q
+    pub fn rotate_vector(&self, v: V3) -> V3 {
+        let p = Quaternion {
+            w: 0.0,
+            x: v.x,
+            y: v.y,
+            z: v.z,
+        };
+        let r = self.conjugate() * p * *self;
+
+        v3![r.x, r.y, r.z]
+    }
+
+    // q0からq1へdt秒で回転するときの角速度(回転軸 * 角速度)
+    // 差分 d = q0^-1 * q1 の対数から求める。短い方の回転を選ぶ
+    pub fn angular_velocity_between(q0: &Quaternion, q1: &Quaternion, dt: f64) -> V3 {
+        let mut d = q0.conjugate() * *q1;
+        if d.w < 0.0 {
+            d = Quaternion {
+                w: -d.w,
+                x: -d.x,
+                y: -d.y,
+                z: -d.z,
+            };
+        }
+
+        let log = d.log();
+        v3![log.x, log.y, log.z] * (2.0 / dt)
+    }
+
+    // 角速度omegaでdt秒回転させた向き。angular_velocity_betweenの逆
+    pub fn apply_angular_velocity(&self, omega: V3, dt: f64) -> Quaternion {
+        let half = omega * (0.5 * dt);
+        let d = Quaternion {
+            w: 0.0,
+            x: half.x,
+            y: half.y,
+            z: half.z,
+        }
+        .exp();
+
+        *self * d
+    }
+
+    // 角速度omegaでdt秒の微小回転を近似で合成する。1kHz以上の積分ループ向け
+    // 誤差がtoleranceを超える大きな回転では厳密な計算に切り替える
+    // 近似の結果は正規化しない。長く積分するときは呼び出し側でときどきnormalizeする
+    pub fn apply_small_rotation(&self, omega: V3, dt: f64, tolerance: f64) -> Quaternion {
+        let half = omega * (0.5 * dt);
+        let alpha_sq = half.mag_squared();
+
+        let (c, k) = match SmallAngleOrder::select(alpha_sq, tolerance) {
+            SmallAngleOrder::First => (1.0, 1.0),
+            SmallAngleOrder::Second => (1.0 - alpha_sq * 0.5, 1.0 - alpha_sq / 6.0),
+            SmallAngleOrder::Exact => return self.apply_angular_velocity(omega, dt),
+        };
+
+        let d = Quaternion {
+            w: c,
+            x: half.x * k,
+            y: half.y * k,
+            z: half.z * k,
+        };
+
+        *self * d
+    }
+
+    // +Zがforward、+Yができるだけupを向く姿勢に、forwardまわりのroll回転を加える
+    // forwardとupが平行なときは+X(forwardがX軸に近いときは+Z)をupの代わりに使う
+    pub fn look_at_with_roll(forward: V3, up: V3, roll: f64) -> Quaternion {
+        let forward = forward.normalize();
+        let mut right = up.cross(&forward);
+        if right.mag_squared() < 1e-12 {
+            let fallback = if forward.x.abs() < 0.9 {
+                v3![1.0, 0.0, 0.0]
+            } else {
+                v3![0.0, 0.0, 1.0]
+            };
+            right = fallback.cross(&forward);
+        }
+        let right = right.normalize();
+        let up = forward.cross(&right);
+
+        let base = Quaternion::from_matrix(&Matrix3x4::from_array([
+            [right.x, right.y, right.z],
+            [up.x, up.y, up.z],
+            [forward.x, forward.y, forward.z],
+            [0.0, 0.0, 0.0],
+        ]));
+        let roll = Quaternion {
+            w: 0.0,
+            x: 0.0,
+            y: 0.0,
+            z: roll * 0.5,
+        }
+        .exp();
+
+        // ローカルのrollのあとに向きを合わせる
+        roll * base
+    }
+
+    pub fn look_at_with_up(forward: V3, up: UpVector, roll: f64) -> Quaternion {
+        Quaternion::look_at_with_roll(forward, up.resolve(), roll)
+    }
+
+    // 回転行列(3x3部分)から変換。最も大きい成分から求めて精度を保つ
+    pub fn from_matrix(m: &Matrix3x4) -> Quaternion {
+        debug_assert_finite!(m);
+
+        let w_sq = m.m11 + m.m22 + m.m33;
+        let x_sq = m.m11 - m.m22 - m.m33;
+        let y_sq = m.m22 - m.m11 - m.m33;
+        let z_sq = m.m33 - m.m11 - m.m22;
+
+        let mut biggest_index = 0;
+        let mut biggest_sq = w_sq;
+        for (i, sq) in [x_sq, y_sq, z_sq].into_iter().enumerate() {
+            if sq > biggest_sq {
+                biggest_sq = sq;
+                biggest_index = i + 1;
+            }
+        }
+
+        let biggest = (biggest_sq + 1.0).sqrt() * 0.5;
+        let mult = 0.25 / biggest;
+
+        match biggest_index {
+            0 => Quaternion {
+                w: biggest,
+                x: (m.m23 - m.m32) * mult,
+                y: (m.m31 - m.m13) * mult,
+                z: (m.m12 - m.m21) * mult,
+            },
+            1 => Quaternion {
+                w: (m.m23 - m.m32) * mult,
+                x: biggest,
+                y: (m.m12 + m.m21) * mult,
+                z: (m.m31 + m.m13) * mult,
+            },
+            2 => Quaternion {
+                w: (m.m31 - m.m13) * mult,
+                x: (m.m12 + m.m21) * mult,
+                y: biggest,
+                z: (m.m23 + m.m32) * mult,
+            },
+            _ => Quaternion {
+                w: (m.m12 - m.m21) * mult,
+                x: (m.m31 + m.m13) * mult,
+                y: (m.m23 + m.m32) * mult,
+                z: biggest,
+            },
+        }
+    }
+
+    // axisまわりのねじれ(twist)と、axisに垂直な軸まわりの振り(swing)に分解する
+    // この積の順序では self = twist * swing (twistを先に適用する)。axisは単位ベクトル
+    pub fn swing_twist(&self, axis: V3) -> (Quaternion, Quaternion) {
+        let d = self.x * axis.x + self.y * axis.y + self.z * axis.z;
+        let twist = Quaternion {
+            w: self.w,
+            x: axis.x * d,
+            y: axis.y * d,
+            z: axis.z * d,
+        }
+        // 180度の振りではねじれの成分が0になり、ねじれは決まらない
+        .normalize_or_identity();
+        let swing = twist.conjugate() * *self;
+
+        (swing, twist)
+    }
+
+    // w >= 0 に揃える。w == 0 の場合は最初の非ゼロ成分を正にする
+    pub fn canonicalized(&self) -> Quaternion {
+        let sign = [self.w, self.x, self.y, self.z]
+            .into_iter()
+            .find(|c| *c != 0.0)
+            .map_or(1.0, f64::signum);
+
+        Quaternion {
+            w: self.w * sign,
+            x: self.x * sign,
+            y: self.y * sign,
+            z: self.z * sign,
+        }
+    }
+
+    // qと-qは同じ回転を表すので、どちらかと近ければ等しいとみなす
+    pub fn approx_eq_rotation(&self, other: &Quaternion, epsilon: f64) -> bool {
+        let near = |sign: f64| {
+            (self.w - sign * other.w).abs() <= epsilon
+                && (self.x - sign * other.x).abs() <= epsilon
+                && (self.y - sign * other.y).abs() <= epsilon
+                && (self.z - sign * other.z).abs() <= epsilon
+        };
+
+        near(1.0) || near(-1.0)
+    }
+
+    // 先に量子化してから符号を揃える。wが0付近のとき、量子化前に揃えると
+    // qと-qで揃え方が分かれて別のキーになってしまう
+    pub fn rotation_key(&self, epsilon: f64) -> QuaternionKey {
+        let quantize = |c: f64| (c / epsilon).round() as i64;
+        let key = [
+            quantize(self.w),
+            quantize(self.x),
+            quantize(self.y),
+            quantize(self.z),
+        ];
+        let sign = key.iter().find(|c| **c != 0).map_or(1, |c| c.signum());
+
+        QuaternionKey {
+            w: key[0] * sign,
+            x: key[1] * sign,
+            y: key[2] * sign,
+            z: key[3] * sign,
+        }
+    }
 }
 
 impl Mul for Quaternion {
@@ -216,3 +563,287 @@ impl MulAssign for Quaternion {
         *self = *self * rhs;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: &Quaternion, b: &Quaternion, epsilon: f64) -> bool {
+        (a.w - b.w).abs() < epsilon
+            && (a.x - b.x).abs() < epsilon
+            && (a.y - b.y).abs() < epsilon
+            && (a.z - b.z).abs() < epsilon
+    }
+
+    fn rotated(axis: V3, theta: f64) -> Quaternion {
+        let mut q = Quaternion::identitiy();
+        q.rotate_axis(axis.normalize(), theta);
+        q
+    }
+
+    #[test]
+    fn log_exp_round_trip() {
+        for q in [
+            rotated(v3!(0.0, 0.6, 0.8), 0.7),
+            rotated(v3!(1.0, -2.0, 0.5), 3.0),
+            rotated(v3!(0.0, 0.0, 1.0), 1e-12),
+            Quaternion::identitiy(),
+        ] {
+            assert!(close(&q.log().exp(), &q, 1e-12));
+        }
+
+        // logは純虚四元数で、長さは回転角の半分
+        let l = rotated(v3!(1.0, 0.0, 0.0), 1.2).log();
+        assert_eq!(l.w, 0.0);
+        assert!((l.x - 0.6).abs() < 1e-12);
+    }
+
+    #[test]
+    fn slerp_takes_short_arc() {
+        let a = Quaternion::identitiy();
+        let b = rotated(v3!(1.0, 0.0, 0.0), 1.0);
+        let half = rotated(v3!(1.0, 0.0, 0.0), 0.5);
+        assert!(close(&a.slerp(b, 0.5), &half, 1e-12));
+        assert!(close(&a.slerp(b, 0.0), &a, 1e-12));
+        assert!(close(&a.slerp(b, 1.0), &b, 1e-12));
+
+        // -bは同じ回転なので、符号を反転して短い方の弧を通る
+        let neg = Quaternion {
+            w: -b.w,
+            x: -b.x,
+            y: -b.y,
+            z: -b.z,
+        };
+        assert!(a.slerp(neg, 0.5).approx_eq_rotation(&half, 1e-12));
+        assert!(a
+            .slerp(neg, 0.25)
+            .approx_eq_rotation(&rotated(v3!(1.0, 0.0, 0.0), 0.25), 1e-12));
+    }
+
+    #[test]
+    fn double_cover() {
+        let q = rotated(v3!(0.0, 0.6, 0.8), 2.0);
+        let neg = Quaternion {
+            w: -q.w,
+            x: -q.x,
+            y: -q.y,
+            z: -q.z,
+        };
+
+        assert!(q.canonicalized().w >= 0.0);
+        assert_eq!(neg.canonicalized(), q.canonicalized());
+        // w == 0 のときは最初の非ゼロ成分を正にする
+        let half_turn = Quaternion {
+            w: 0.0,
+            x: -1.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        assert_eq!(half_turn.canonicalized().x, 1.0);
+
+        assert!(q.approx_eq_rotation(&neg, 1e-12));
+        assert!(!q.approx_eq_rotation(&rotated(v3!(0.0, 0.6, 0.8), 2.1), 1e-3));
+
+        assert_eq!(q.rotation_key(1e-6), neg.rotation_key(1e-6));
+        assert_ne!(
+            q.rotation_key(1e-6),
+            rotated(v3!(0.0, 0.6, 0.8), 2.1).rotation_key(1e-6)
+        );
+    }
+
+    #[test]
+    fn rotation_key_near_zero_w() {
+        // 180度付近で w が量子化幅より小さい符号違いの値になる場合
+        let mut q = rotated(v3!(1.0, 2.0, -0.5), std::f64::consts::PI);
+        q.w = 1e-9;
+        let neg = Quaternion {
+            w: -q.w,
+            x: -q.x,
+            y: -q.y,
+            z: -q.z,
+        };
+        assert_eq!(q.rotation_key(1e-6), neg.rotation_key(1e-6));
+
+        let mut other = q;
+        other.w = -1e-9;
+        assert_eq!(q.rotation_key(1e-6), other.rotation_key(1e-6));
+    }
+
+    #[test]
+    fn swing_twist_decomposition() {
+        let axis = v3!(0.0, 1.0, 0.0);
+        let twist = rotated(axis, 0.8);
+        let swing = rotated(v3!(1.0, 0.0, 2.0), 0.6);
+        let q = twist * swing;
+
+        let (s, t) = q.swing_twist(axis);
+        assert!(close(&s, &swing, 1e-12));
+        assert!(close(&t, &twist, 1e-12));
+        assert!(close(&(t * s), &q, 1e-12));
+
+        // 振りは軸を動かし、ねじれは動かさない
+        assert!((s.rotate_vector(axis) - q.rotate_vector(axis)).mag() < 1e-12);
+        assert!((t.rotate_vector(axis) - axis).mag() < 1e-12);
+
+        // 180度の振りではねじれは単位元
+        let half_turn = rotated(v3!(1.0, 0.0, 0.0), std::f64::consts::PI);
+        let (s, t) = half_turn.swing_twist(axis);
+        assert_eq!(t, Quaternion::identitiy());
+        assert!(close(&s, &half_turn, 1e-12));
+    }
+
+    #[test]
+    fn look_at() {
+        let near = |a: V3, b: V3| (a - b).mag() < 1e-9;
+        let y = v3!(0.0, 1.0, 0.0);
+        let z = v3!(0.0, 0.0, 1.0);
+
+        // +Zがforwardに移り、+Yはforwardに垂直でupの側を向く
+        let f = v3!(1.0, 2.0, -0.5).normalize();
+        let q = Quaternion::look_at_with_roll(f, y, 0.0);
+        assert!(near(q.rotate_vector(z), f));
+        let up = q.rotate_vector(y);
+        assert!(up.y > 0.0 && up.dot(&f).abs() < 1e-9);
+        assert!(up.cross(&y).dot(&f).abs() < 1e-9);
+
+        // rollはforwardまわりの回転で、forwardは変わらない
+        let r = Quaternion::look_at_with_roll(f, y, 0.7);
+        assert!(near(r.rotate_vector(z), f));
+        let rolled = r.rotate_vector(y);
+        assert!((rolled.dot(&up) - 0.7f64.cos()).abs() < 1e-9);
+        assert!((rolled.cross(&up).dot(&f).abs() - 0.7f64.sin()).abs() < 1e-9);
+
+        // forwardとupが平行なら+Xをupに使う
+        let d = Quaternion::look_at_with_roll(y, y, 0.0);
+        assert!(near(d.rotate_vector(z), y));
+        assert!(near(d.rotate_vector(y), v3!(1.0, 0.0, 0.0)));
+        // forwardがX軸に近ければ+Z
+        let x = v3!(1.0, 0.0, 0.0);
+        let d = Quaternion::look_at_with_roll(x, x, 0.0);
+        assert!(near(d.rotate_vector(z), x));
+        assert!(near(d.rotate_vector(y), z));
+    }
+
+    #[test]
+    fn up_vector_modes() {
+        let world = v3!(0.0, 1.0, 0.0);
+        let path = v3!(1.0, 0.0, 0.0);
+        assert_eq!(UpVector::World(world).resolve(), world);
+        assert_eq!(UpVector::Path(path).resolve(), path);
+        let blend = UpVector::Blend {
+            world,
+            path,
+            blend: 0.5,
+        };
+        assert!((blend.resolve() - v3!(1.0, 1.0, 0.0).normalize()).mag() < 1e-9);
+
+        let f = v3!(0.0, 0.0, 1.0);
+        let q = Quaternion::look_at_with_up(f, UpVector::Path(path), 0.0);
+        assert!((q.rotate_vector(world) - path).mag() < 1e-9);
+    }
+
+    #[test]
+    fn try_normalize() {
+        let zero = Quaternion {
+            w: 0.0,
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        assert_eq!(zero.try_normalize(), None);
+        assert_eq!(zero.normalize_or_identity(), Quaternion::identitiy());
+
+        let nan = Quaternion {
+            x: f64::NAN,
+            ..zero
+        };
+        assert_eq!(nan.try_normalize(), None);
+        assert_eq!(nan.normalize_or_identity(), Quaternion::identitiy());
+        let fallback = rotated(v3!(1.0, 0.0, 0.0), 0.5);
+        assert_eq!(nan.normalize_or(fallback), fallback);
+
+        let unit = rotated(v3!(0.0, 0.6, 0.8), 0.7);
+        assert!(close(&unit.try_normalize().unwrap(), &unit, 1e-15));
+        assert!(close(&unit.normalize_or_identity(), &unit, 1e-15));
+
+        let scaled = Quaternion {
+            w: 2.0,
+            x: 0.0,
+            y: 0.0,
+            z: 2.0,
+        };
+        let half = 0.5f64.sqrt();
+        assert!(close(
+            &scaled.try_normalize().unwrap(),
+            &Quaternion {
+                w: half,
+                x: 0.0,
+                y: 0.0,
+                z: half
+            },
+            1e-15
+        ));
+    }
+
+    #[test]
+    fn small_rotation_within_tolerance() {
+        let q0 = rotated(v3!(0.0, 0.6, 0.8), 0.7);
+        let axis = v3!(0.3, -1.2, 0.5).normalize();
+        let tolerance = 1e-6;
+        let dt = 1e-3;
+
+        // 半角alphaが各近似の選ばれる範囲に入るよう角速度の大きさを変える
+        for (alpha, order) in [
+            (1e-4, SmallAngleOrder::First),
+            (1e-3, SmallAngleOrder::First),
+            (5e-3, SmallAngleOrder::Second),
+            (0.05, SmallAngleOrder::Second),
+            (0.2, SmallAngleOrder::Exact),
+        ] {
+            assert_eq!(SmallAngleOrder::select(alpha * alpha, tolerance), order);
+
+            let omega = axis * (2.0 * alpha / dt);
+            let approx = q0.apply_small_rotation(omega, dt, tolerance);
+            let exact = q0.apply_angular_velocity(omega, dt);
+            assert!(close(&approx, &exact, tolerance), "{:?}", order);
+        }
+
+        // 1kHzで1秒積分しても、最後に正規化すれば向きの誤差は積み上がらない
+        let omega = v3!(0.3, -1.2, 0.5);
+        let mut a = q0;
+        let mut b = q0;
+        for _ in 0..1000 {
+            a = a.apply_small_rotation(omega, dt, tolerance);
+            b = b.apply_angular_velocity(omega, dt);
+        }
+        a.normalize();
+        assert!(a.approx_eq_rotation(&b, tolerance));
+    }
+
+    #[test]
+    fn angular_velocity_round_trip() {
+        let q0 = rotated(v3!(0.0, 0.6, 0.8), 0.7);
+        let q1 = rotated(v3!(-1.0, 0.3, 0.2), 2.1);
+        let dt = 0.25;
+
+        let omega = Quaternion::angular_velocity_between(&q0, &q1, dt);
+        assert!(q0
+            .apply_angular_velocity(omega, dt)
+            .approx_eq_rotation(&q1, 1e-9));
+
+        let omega = v3!(0.3, -1.2, 0.5);
+        let q2 = q0.apply_angular_velocity(omega, dt);
+        let back = Quaternion::angular_velocity_between(&q0, &q2, dt);
+        assert!((back - omega).mag() < 1e-9);
+
+        // -q1 でも短い方の回転を選ぶ
+        let neg = Quaternion {
+            w: -q1.w,
+            x: -q1.x,
+            y: -q1.y,
+            z: -q1.z,
+        };
+        let omega_neg = Quaternion::angular_velocity_between(&q0, &neg, dt);
+        assert!((omega_neg - Quaternion::angular_velocity_between(&q0, &q1, dt)).mag() < 1e-9);
+    }
+}
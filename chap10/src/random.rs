@@ -0,0 +1,91 @@
+use std::f64::consts::PI;
+
+use rand::Rng;
+
+use crate::{v3, vector::V3};
+
+// 単位球面上で一様な方向。zを一様に取ると面積も一様になる(アルキメデスの定理)
+pub fn unit_vector<R: Rng + ?Sized>(rng: &mut R) -> V3 {
+    let z: f64 = rng.gen_range(-1.0..=1.0);
+    let phi: f64 = rng.gen_range(0.0..2.0 * PI);
+    let r = (1.0 - z * z).max(0.0).sqrt();
+
+    v3!(r * phi.cos(), r * phi.sin(), z)
+}
+
+pub fn on_sphere<R: Rng + ?Sized>(rng: &mut R, center: V3, radius: f64) -> V3 {
+    center + unit_vector(rng) * radius
+}
+
+// 球の内部で一様な点。体積は半径の3乗に比例するので、半径は一様乱数の立方根
+pub fn in_sphere<R: Rng + ?Sized>(rng: &mut R, center: V3, radius: f64) -> V3 {
+    let u: f64 = rng.gen();
+
+    center + unit_vector(rng) * (radius * u.cbrt())
+}
+
+// AABB (min, max) の内部で一様な点
+pub fn in_aabb<R: Rng + ?Sized>(rng: &mut R, min: V3, max: V3) -> V3 {
+    v3!(
+        min.x + (max.x - min.x) * rng.gen::<f64>(),
+        min.y + (max.y - min.y) * rng.gen::<f64>(),
+        min.z + (max.z - min.z) * rng.gen::<f64>(),
+    )
+}
+
+// 単位ベクトルnormalのまわりの半球で、cosθに比例する密度の方向。pdfは cosθ / π
+// 単位円盤上の一様な点を半球に持ち上げる(Malleyの方法)
+pub fn cosine_hemisphere<R: Rng + ?Sized>(rng: &mut R, normal: V3) -> V3 {
+    let u1: f64 = rng.gen();
+    let phi: f64 = rng.gen_range(0.0..2.0 * PI);
+    let r = u1.sqrt();
+
+    let (tangent, bitangent) = normal.any_orthonormal_basis();
+    tangent * (r * phi.cos()) + bitangent * (r * phi.sin()) + normal * (1.0 - u1).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn sphere_samples() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let center = v3!(1.0, 2.0, 3.0);
+        let mut sum = v3!(0.0, 0.0, 0.0);
+
+        for _ in 0..1000 {
+            let d = unit_vector(&mut rng);
+            assert!((d.mag() - 1.0).abs() < 1e-12);
+            sum += d;
+
+            assert!((on_sphere(&mut rng, center, 2.0).distance(&center) - 2.0).abs() < 1e-12);
+            assert!(in_sphere(&mut rng, center, 2.0).distance(&center) <= 2.0);
+        }
+
+        // 偏りがなければ平均はほぼ0
+        assert!((sum / 1000.0).mag() < 0.1);
+    }
+
+    #[test]
+    fn aabb_and_hemisphere_samples() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let (min, max) = (v3!(-1.0, 0.0, 2.0), v3!(1.0, 0.5, 4.0));
+        let normal = v3!(0.0, 1.0, 1.0).normalize();
+        let mut cos_sum = 0.0;
+
+        for _ in 0..1000 {
+            let p = in_aabb(&mut rng, min, max);
+            assert_eq!(p.clamp(&min, &max), p);
+
+            let d = cosine_hemisphere(&mut rng, normal);
+            assert!((d.mag() - 1.0).abs() < 1e-9);
+            assert!(d.dot(&normal) >= 0.0);
+            cos_sum += d.dot(&normal);
+        }
+
+        // cosθの期待値は 2/3
+        assert!((cos_sum / 1000.0 - 2.0 / 3.0).abs() < 0.05);
+    }
+}
@@ -0,0 +1,143 @@
+use crate::{euler_angles::EulerAngles, quaternion::Quaternion, se3::Se3, v3, vector::V3};
+
+// アニメーションのルート(腰など)の動きのうち、キャラクターを動かす分
+// 地面(y = 0 の平面)上の移動と、上方向(y軸)まわりの回転だけを持つ
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RootMotion {
+    // 前の姿勢の向きから見た移動量。yは常に0
+    pub translation: V3,
+    // y軸まわりの回転角 [-PI, PI]
+    pub heading: f64,
+}
+
+fn up() -> V3 {
+    v3!(0.0, 1.0, 0.0)
+}
+
+// 向きのうちheadingだけを残す。pitchとbankは捨てる
+// y軸まわりのswing_twistのねじれとは違い、前方向を地面に投影した向きになる
+pub fn heading_rotation(q: Quaternion) -> Quaternion {
+    let mut orientation = EulerAngles::from_obj_to_inertial_quaternion(q);
+    // 真上や真下を向いているときはbankをheadingに移す
+    orientation.canonize();
+
+    let mut result = Quaternion::identitiy();
+    result.rotate_axis(up(), orientation.heading);
+    result
+}
+
+// 姿勢を地面に投影する。向きはheadingだけ、位置は高さを落とす
+pub fn ground_pose(pose: &Se3) -> Se3 {
+    let t = pose.translation;
+    Se3::new(heading_rotation(pose.rotation), v3!(t.x, 0.0, t.z))
+}
+
+impl RootMotion {
+    // 連続する2つのサンプルの間のルートモーション
+    pub fn extract(prev: &Se3, curr: &Se3) -> RootMotion {
+        let prev = ground_pose(prev);
+        let curr = ground_pose(curr);
+
+        // curr = delta * prev となる、prevの座標系での変化
+        let delta = curr * prev.inverse();
+
+        // y軸まわりだけの回転なので、pitchとbankは0でheadingが[-PI, PI]に収まる
+        let heading = EulerAngles::from_obj_to_inertial_quaternion(delta.rotation).heading;
+
+        RootMotion {
+            translation: delta.translation,
+            heading,
+        }
+    }
+
+    pub fn rotation(&self) -> Quaternion {
+        let mut result = Quaternion::identitiy();
+        result.rotate_axis(up(), self.heading);
+        result
+    }
+
+    // キャラクターの姿勢に適用する。移動はキャラクターのheadingの向きで地面に沿って行い、
+    // 回転はワールドのy軸まわりに加える。キャラクターの傾きや高さはそのまま
+    pub fn apply(&self, character: &Se3) -> Se3 {
+        let facing = heading_rotation(character.rotation);
+
+        Se3::new(
+            character.rotation * self.rotation(),
+            character.translation + facing.rotate_vector(self.translation),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::approx::ApproxEq;
+
+    fn pose(heading: f64, pitch: f64, bank: f64, translation: V3) -> Se3 {
+        let mut q = Quaternion::identitiy();
+        q.rotate_obj_to_inertial(EulerAngles {
+            heading,
+            pitch,
+            bank,
+        });
+        Se3::new(q, translation)
+    }
+
+    #[test]
+    fn extract_and_apply() {
+        let prev = pose(0.4, 0.0, 0.0, v3!(1.0, 0.0, 2.0));
+        let curr = pose(0.7, 0.0, 0.0, v3!(1.5, 0.0, 2.5));
+
+        let motion = RootMotion::extract(&prev, &curr);
+        assert!((motion.heading - 0.3).abs() < 1e-9);
+        assert_eq!(motion.translation.y, 0.0);
+
+        // 同じ姿勢から適用すれば次のサンプルに戻る
+        let next = motion.apply(&prev);
+        assert!(next.rotation.approx_eq_rotation(&curr.rotation, 1e-9));
+        assert!(next.translation.abs_diff_eq(&curr.translation, 1e-9));
+
+        // 向きの違うキャラクターでは、そのキャラクターの前方を基準に動く
+        let other = pose(0.4 + 1.0, 0.0, 0.0, v3!(0.0, 0.0, 0.0));
+        let moved = motion.apply(&other);
+        let step = curr.translation - prev.translation;
+        assert!((moved.translation.mag() - step.mag()).abs() < 1e-9);
+        let mut turn = Quaternion::identitiy();
+        turn.rotate_axis(up(), 1.0);
+        assert!(moved
+            .translation
+            .abs_diff_eq(&turn.rotate_vector(step), 1e-9));
+    }
+
+    #[test]
+    fn drops_pitch_bank_and_height() {
+        let prev = pose(0.4, 0.0, 0.0, v3!(1.0, 0.0, 2.0));
+        let curr = pose(0.7, 0.0, 0.0, v3!(1.5, 0.0, 2.5));
+        let bobbing = pose(0.7, 0.2, -0.3, v3!(1.5, 0.8, 2.5));
+
+        let a = RootMotion::extract(&prev, &curr);
+        let b = RootMotion::extract(&prev, &bobbing);
+        assert!((a.heading - b.heading).abs() < 1e-9);
+        assert!(a.translation.abs_diff_eq(&b.translation, 1e-9));
+
+        // 傾いたキャラクターに適用しても、高さと傾きは変わらない
+        let character = pose(0.0, 0.1, 0.2, v3!(0.0, 3.0, 0.0));
+        let moved = a.apply(&character);
+        assert_eq!(moved.translation.y, 3.0);
+        let before = EulerAngles::from_obj_to_inertial_quaternion(character.rotation);
+        let after = EulerAngles::from_obj_to_inertial_quaternion(moved.rotation);
+        assert!((after.heading - before.heading - a.heading).abs() < 1e-9);
+        assert!((after.pitch - before.pitch).abs() < 1e-9);
+        assert!((after.bank - before.bank).abs() < 1e-9);
+    }
+
+    #[test]
+    fn heading_wraps() {
+        let prev = pose(3.0, 0.0, 0.0, v3!(0.0, 0.0, 0.0));
+        let curr = pose(-3.0, 0.0, 0.0, v3!(0.0, 0.0, 0.0));
+
+        // 3 -> -3 は 2PI - 6 だけ正の向きに回る
+        let motion = RootMotion::extract(&prev, &curr);
+        assert!((motion.heading - (2.0 * std::f64::consts::PI - 6.0)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,288 @@
+use std::ops::{Mul, MulAssign};
+
+use crate::{quaternion::Quaternion, v3, vector::V3};
+
+// 回転ベクトル(回転軸 * 角度)と並進速度の組。se(3)の要素
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Twist {
+    pub angular: V3,
+    pub linear: V3,
+}
+
+impl Twist {
+    pub fn new(angular: V3, linear: V3) -> Self {
+        Twist { angular, linear }
+    }
+
+    pub fn to_array(&self) -> [f64; 6] {
+        [
+            self.angular.x,
+            self.angular.y,
+            self.angular.z,
+            self.linear.x,
+            self.linear.y,
+            self.linear.z,
+        ]
+    }
+
+    pub fn from_array(a: [f64; 6]) -> Self {
+        Twist::new(v3!(a[0], a[1], a[2]), v3!(a[3], a[4], a[5]))
+    }
+}
+
+// 剛体変換 p' = R(p) + t をリー群として扱う
+// 積は四元数や行列と同じく a * b が「aのあとにb」
+#[derive(Debug, Clone, Copy)]
+pub struct Se3 {
+    pub rotation: Quaternion,
+    pub translation: V3,
+}
+
+// exp/logに現れる (1 - cos θ) / θ^2 と (θ - sin θ) / θ^3。θが小さいときは級数展開する
+fn coefficients(theta: f64) -> (f64, f64) {
+    let theta_sq = theta * theta;
+    if theta < 1e-4 {
+        return (0.5 - theta_sq / 24.0, 1.0 / 6.0 - theta_sq / 120.0);
+    }
+
+    let (s, c) = theta.sin_cos();
+    ((1.0 - c) / theta_sq, (theta - s) / (theta_sq * theta))
+}
+
+// 回転ベクトルωに対する左ヤコビアンV = I + a[ω]x + b[ω]x^2 をvにかける
+fn apply_v(omega: V3, v: V3, a: f64, b: f64) -> V3 {
+    let wv = omega.cross(&v);
+    v + wv * a + omega.cross(&wv) * b
+}
+
+impl Se3 {
+    pub fn new(rotation: Quaternion, translation: V3) -> Self {
+        Se3 {
+            rotation,
+            translation,
+        }
+    }
+
+    pub fn identity() -> Self {
+        Se3::new(
+            Quaternion {
+                w: 1.0,
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            v3!(0.0, 0.0, 0.0),
+        )
+    }
+
+    pub fn transform_point(&self, p: V3) -> V3 {
+        self.rotation.rotate_vector(p) + self.translation
+    }
+
+    pub fn transform_vector(&self, v: V3) -> V3 {
+        self.rotation.rotate_vector(v)
+    }
+
+    pub fn inverse(&self) -> Se3 {
+        let rotation = self.rotation.conjugate();
+        let translation = -rotation.rotate_vector(self.translation);
+
+        Se3::new(rotation, translation)
+    }
+
+    // 指数写像 se(3) -> SE(3)
+    pub fn exp(twist: Twist) -> Se3 {
+        let omega = twist.angular;
+        let half = omega * 0.5;
+        let rotation = Quaternion {
+            w: 0.0,
+            x: half.x,
+            y: half.y,
+            z: half.z,
+        }
+        .exp();
+
+        let (a, b) = coefficients(omega.mag());
+        Se3::new(rotation, apply_v(omega, twist.linear, a, b))
+    }
+
+    // 対数写像 SE(3) -> se(3)。回転は短い方(θ <= π)を選ぶ
+    pub fn log(&self) -> Twist {
+        let q = &self.rotation;
+        let sign = if q.w < 0.0 { -1.0 } else { 1.0 };
+        let log = Quaternion {
+            w: q.w * sign,
+            x: q.x * sign,
+            y: q.y * sign,
+            z: q.z * sign,
+        }
+        .log();
+        let omega = v3!(log.x, log.y, log.z) * 2.0;
+        let theta = omega.mag();
+
+        // V^-1 = I - [ω]x / 2 + c[ω]x^2
+        let c = if theta < 1e-4 {
+            1.0 / 12.0 + theta * theta / 720.0
+        } else {
+            let (s, cos) = theta.sin_cos();
+            (1.0 - theta * s / (2.0 * (1.0 - cos))) / (theta * theta)
+        };
+        let linear = apply_v(omega, self.translation, -0.5, c);
+
+        Twist::new(omega, linear)
+    }
+
+    // ツイストの座標を変換する随伴行列 [[R, 0], [[t]x R, R]]
+    // 関数として T ∘ exp(ξ) ∘ T^-1 = exp(Ad ξ) を満たす
+    pub fn adjoint(&self) -> [[f64; 6]; 6] {
+        let mut result = [[0.0; 6]; 6];
+
+        let axes = [v3!(1.0, 0.0, 0.0), v3!(0.0, 1.0, 0.0), v3!(0.0, 0.0, 1.0)];
+        for (j, axis) in axes.iter().enumerate() {
+            let r = self.rotation.rotate_vector(*axis);
+            let tr = self.translation.cross(&r);
+            for i in 0..3 {
+                result[i][j] = r[i];
+                result[i + 3][j] = tr[i];
+                result[i + 3][j + 3] = r[i];
+            }
+        }
+
+        result
+    }
+
+    pub fn adjoint_apply(&self, twist: Twist) -> Twist {
+        let angular = self.rotation.rotate_vector(twist.angular);
+        let linear = self.translation.cross(&angular) + self.rotation.rotate_vector(twist.linear);
+
+        Twist::new(angular, linear)
+    }
+
+    // selfからotherへの相対変換 d (self * d = other)
+    pub fn between(&self, other: &Se3) -> Se3 {
+        self.inverse() * *other
+    }
+
+    // 一定のツイストで動くねじ運動による補間。回転と並進を別々に補間するのと違い、
+    // 回転軸まわりのらせんに沿って動く
+    pub fn screw_interpolate(&self, other: &Se3, t: f64) -> Se3 {
+        let d = self.between(other).log();
+
+        *self * Se3::exp(Twist::new(d.angular * t, d.linear * t))
+    }
+
+    // ポーズグラフの辺の誤差。観測された相対変換measuredと推定値の相対変換の差をse(3)で返す
+    // 推定が観測と一致すればゼロ
+    pub fn between_error(&self, other: &Se3, measured: &Se3) -> Twist {
+        (measured.inverse() * self.between(other)).log()
+    }
+}
+
+impl Mul for Se3 {
+    type Output = Se3;
+
+    // aのあとにb: b.R(a.R(p) + a.t) + b.t
+    fn mul(self, rhs: Self) -> Self::Output {
+        Se3::new(
+            self.rotation * rhs.rotation,
+            rhs.rotation.rotate_vector(self.translation) + rhs.translation,
+        )
+    }
+}
+
+impl MulAssign for Se3 {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn twist() -> Twist {
+        Twist::new(v3!(0.3, -0.4, 1.2), v3!(1.0, 2.0, -0.5))
+    }
+
+    fn close(a: V3, b: V3) -> bool {
+        (a - b).mag() < 1e-9
+    }
+
+    #[test]
+    fn exp_log_roundtrip() {
+        for xi in [twist(), Twist::new(v3!(1e-6, 0.0, 0.0), v3!(0.0, 3.0, 0.0))] {
+            let back = Se3::exp(xi).log();
+            assert!(close(back.angular, xi.angular));
+            assert!(close(back.linear, xi.linear));
+        }
+    }
+
+    #[test]
+    fn inverse_and_between() {
+        let a = Se3::exp(twist());
+        let b = Se3::exp(Twist::new(v3!(-0.2, 0.1, 0.0), v3!(0.0, 0.0, 4.0)));
+        let p = v3!(1.0, -1.0, 2.0);
+
+        assert!(close(a.inverse().transform_point(a.transform_point(p)), p));
+        assert!(close(
+            (a * a.between(&b)).transform_point(p),
+            b.transform_point(p)
+        ));
+
+        let error = a.between_error(&b, &a.between(&b));
+        assert!(error.angular.mag() < 1e-9 && error.linear.mag() < 1e-9);
+    }
+
+    #[test]
+    fn screw_interpolation() {
+        // 並進だけなら直線
+        let a = Se3::exp(Twist::new(v3!(0.0, 0.0, 0.0), v3!(1.0, 2.0, 3.0)));
+        let b = Se3::exp(Twist::new(v3!(0.0, 0.0, 0.0), v3!(3.0, 2.0, -1.0)));
+        let mid = a.screw_interpolate(&b, 0.25);
+        assert!(close(mid.translation, v3!(1.5, 2.0, 2.0)));
+        assert!(close(
+            mid.rotation.rotate_vector(v3!(1.0, 0.0, 0.0)),
+            v3!(1.0, 0.0, 0.0)
+        ));
+
+        // 原点まわりの回転だけならslerpと同じで並進は0のまま
+        let a = Se3::exp(Twist::new(v3!(0.0, 0.2, 0.0), v3!(0.0, 0.0, 0.0)));
+        let b = Se3::exp(Twist::new(v3!(0.0, 1.4, 0.0), v3!(0.0, 0.0, 0.0)));
+        let mid = a.screw_interpolate(&b, 0.5);
+        let expected = a.rotation.slerp(b.rotation, 0.5);
+        let p = v3!(1.0, 2.0, 3.0);
+        assert!(close(mid.translation, v3!(0.0, 0.0, 0.0)));
+        assert!(close(
+            mid.rotation.rotate_vector(p),
+            expected.rotate_vector(p)
+        ));
+
+        assert!(close(
+            a.screw_interpolate(&b, 1.0).transform_point(p),
+            b.transform_point(p)
+        ));
+    }
+
+    #[test]
+    fn adjoint_conjugates_twists() {
+        let t = Se3::exp(Twist::new(v3!(0.5, 0.2, -0.1), v3!(3.0, 0.0, 1.0)));
+        let xi = twist();
+        let p = v3!(0.5, 1.5, -2.0);
+
+        // 関数合成 T ∘ exp(ξ) ∘ T^-1 はこの積の順序では T^-1 * exp(ξ) * T
+        let conjugated = t.inverse() * Se3::exp(xi) * t;
+        let adjoint = Se3::exp(t.adjoint_apply(xi));
+        assert!(close(
+            conjugated.transform_point(p),
+            adjoint.transform_point(p)
+        ));
+
+        let m = t.adjoint();
+        let x = xi.to_array();
+        let y = t.adjoint_apply(xi).to_array();
+        for i in 0..6 {
+            let row: f64 = (0..6).map(|j| m[i][j] * x[j]).sum();
+            assert!((row - y[i]).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use crate::{matrix::Matrix3x4, quaternion::Quaternion, v3, vector::V3};
+
+// 一辺cellの格子の最も近い点
+pub fn snap_to_grid(v: V3, cell: f64) -> V3 {
+    let snap = |c: f64| (c / cell).round() * cell;
+
+    v3!(snap(v.x), snap(v.y), snap(v.z))
+}
+
+// incrementの倍数の角度に丸める
+pub fn snap_angle(angle: f64, increment: f64) -> f64 {
+    (angle / increment).round() * increment
+}
+
+// 各軸を座標軸(正負)のどれかに重ねる24通りの回転
+// 符号付きの置換行列のうち、行列式が+1(鏡映を含まない)もの
+pub fn axis_aligned_rotations() -> Vec<Quaternion> {
+    const PERMUTATIONS: [([usize; 3], f64); 6] = [
+        ([0, 1, 2], 1.0),
+        ([1, 2, 0], 1.0),
+        ([2, 0, 1], 1.0),
+        ([0, 2, 1], -1.0),
+        ([2, 1, 0], -1.0),
+        ([1, 0, 2], -1.0),
+    ];
+
+    let mut result = Vec::with_capacity(24);
+    for (perm, parity) in PERMUTATIONS {
+        for bits in 0..8 {
+            let signs = [0, 1, 2].map(|i| if bits & (1 << i) == 0 { 1.0 } else { -1.0 });
+            if signs[0] * signs[1] * signs[2] * parity < 0.0 {
+                continue;
+            }
+
+            let mut rows = [[0.0; 3]; 4];
+            for i in 0..3 {
+                rows[i][perm[i]] = signs[i];
+            }
+            result.push(Quaternion::from_matrix(&Matrix3x4::from_array(rows)));
+        }
+    }
+
+    result
+}
+
+// 24通りの軸に沿った向きのうち最も近いもの。qと同じ側の符号で返す
+pub fn snap_to_axis_aligned(q: &Quaternion) -> Quaternion {
+    let dot = |a: &Quaternion| q.w * a.w + q.x * a.x + q.y * a.y + q.z * a.z;
+
+    let best = axis_aligned_rotations()
+        .into_iter()
+        .max_by(|a, b| dot(a).abs().total_cmp(&dot(b).abs()))
+        .unwrap();
+
+    if dot(&best) < 0.0 {
+        Quaternion {
+            w: -best.w,
+            x: -best.x,
+            y: -best.y,
+            z: -best.z,
+        }
+    } else {
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_4;
+
+    #[test]
+    fn grid_and_angle() {
+        assert_eq!(
+            snap_to_grid(v3!(0.26, -0.74, 1.1), 0.5),
+            v3!(0.5, -0.5, 1.0)
+        );
+        assert_eq!(snap_angle(0.8, FRAC_PI_4), FRAC_PI_4);
+        assert_eq!(snap_angle(-2.3, FRAC_PI_4), -3.0 * FRAC_PI_4);
+    }
+
+    #[test]
+    fn axis_aligned_orientations() {
+        let rotations = axis_aligned_rotations();
+        assert_eq!(rotations.len(), 24);
+        for (i, a) in rotations.iter().enumerate() {
+            for b in &rotations[i + 1..] {
+                assert!(!a.approx_eq_rotation(b, 1e-9));
+            }
+        }
+
+        // y軸まわりに約80度回した向きは90度にそろう
+        let angle: f64 = 80.0_f64.to_radians() * 0.5;
+        let q = Quaternion {
+            w: angle.cos(),
+            x: 0.0,
+            y: angle.sin(),
+            z: 0.0,
+        };
+        let snapped = snap_to_axis_aligned(&q);
+        let x = snapped.rotate_vector(v3!(1.0, 0.0, 0.0));
+        assert!((x - v3!(0.0, 0.0, -1.0)).mag() < 1e-9);
+        assert!(snapped.w > 0.0);
+    }
+}
@@ -0,0 +1,204 @@
+use crate::{v3, vector::V3};
+
+const LEAF_SIZE: usize = 4;
+
+struct Node {
+    center: V3,
+    radius: f64,
+    // 葉なら点の範囲、内部ノードなら子のインデックス
+    kind: NodeKind,
+}
+
+enum NodeKind {
+    Leaf { start: usize, end: usize },
+    Inner { left: usize, right: usize },
+}
+
+// 点群に対する境界球の階層
+pub struct SphereTree {
+    points: Vec<V3>,
+    nodes: Vec<Node>,
+}
+
+impl SphereTree {
+    pub fn from_points(points: &[V3]) -> Self {
+        let mut tree = Self {
+            points: points.to_vec(),
+            nodes: Vec::new(),
+        };
+
+        if !points.is_empty() {
+            tree.build(0, points.len());
+        }
+
+        tree
+    }
+
+    // 重心を中心に全点を含む球を作り、最も広がった軸の中央値で分割する
+    fn build(&mut self, start: usize, end: usize) -> usize {
+        let slice = &mut self.points[start..end];
+
+        let center = slice.iter().fold(v3!(0.0, 0.0, 0.0), |acc, p| acc + *p) / slice.len() as f64;
+        let radius = slice
+            .iter()
+            .map(|p| p.distance(&center))
+            .fold(0.0, f64::max);
+
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            center,
+            radius,
+            kind: NodeKind::Leaf { start, end },
+        });
+
+        if end - start <= LEAF_SIZE {
+            return index;
+        }
+
+        let (lo, hi) = slice.iter().fold(
+            (
+                v3!(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+                v3!(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            ),
+            |(lo, hi), p| (lo.min(p), hi.max(p)),
+        );
+        let extent = hi - lo;
+        let key: fn(&V3) -> f64 = if extent.x >= extent.y && extent.x >= extent.z {
+            |p| p.x
+        } else if extent.y >= extent.z {
+            |p| p.y
+        } else {
+            |p| p.z
+        };
+
+        let mid = slice.len() / 2;
+        slice.select_nth_unstable_by(mid, |a, b| key(a).total_cmp(&key(b)));
+
+        let left = self.build(start, start + mid);
+        let right = self.build(start + mid, end);
+        self.nodes[index].kind = NodeKind::Inner { left, right };
+
+        index
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    // 全体を包む球の中心と半径
+    pub fn bounds(&self) -> Option<(V3, f64)> {
+        self.nodes.first().map(|n| (n.center, n.radius))
+    }
+
+    // 最も近い点までの距離。球までの距離で枝刈りする
+    pub fn nearest_distance(&self, p: &V3) -> Option<f64> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let mut best = f64::INFINITY;
+        let mut stack = vec![0];
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if p.distance(&node.center) - node.radius >= best {
+                continue;
+            }
+
+            match node.kind {
+                NodeKind::Leaf { start, end } => {
+                    for q in &self.points[start..end] {
+                        best = best.min(p.distance(q));
+                    }
+                }
+                NodeKind::Inner { left, right } => {
+                    // 近い方を後に積んで先に調べる
+                    let dl = p.distance(&self.nodes[left].center) - self.nodes[left].radius;
+                    let dr = p.distance(&self.nodes[right].center) - self.nodes[right].radius;
+                    if dl < dr {
+                        stack.push(right);
+                        stack.push(left);
+                    } else {
+                        stack.push(left);
+                        stack.push(right);
+                    }
+                }
+            }
+        }
+
+        Some(best)
+    }
+
+    // 距離の下限。根の球だけを見る保守的な判定
+    pub fn distance_lower_bound(&self, p: &V3) -> Option<f64> {
+        self.bounds()
+            .map(|(center, radius)| (p.distance(&center) - radius).max(0.0))
+    }
+
+    // radius以内に点があるか。見つかった時点で打ち切る
+    pub fn any_within(&self, p: &V3, radius: f64) -> bool {
+        let mut stack = if self.nodes.is_empty() {
+            vec![]
+        } else {
+            vec![0]
+        };
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if p.distance(&node.center) - node.radius > radius {
+                continue;
+            }
+
+            match node.kind {
+                NodeKind::Leaf { start, end } => {
+                    if self.points[start..end]
+                        .iter()
+                        .any(|q| p.distance(q) <= radius)
+                    {
+                        return true;
+                    }
+                }
+                NodeKind::Inner { left, right } => {
+                    stack.push(left);
+                    stack.push(right);
+                }
+            }
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_matches_brute_force() {
+        let points: Vec<V3> = (0..50)
+            .map(|i| {
+                let t = i as f64 * 0.37;
+                v3!(t.sin() * 5.0, t.cos() * 3.0, t * 0.1)
+            })
+            .collect();
+        let tree = SphereTree::from_points(&points);
+
+        for q in [v3!(0.0, 0.0, 0.0), v3!(10.0, -2.0, 1.0), v3!(1.0, 1.0, 4.0)] {
+            let expected = points
+                .iter()
+                .map(|p| p.distance(&q))
+                .fold(f64::INFINITY, f64::min);
+            assert_eq!(tree.nearest_distance(&q), Some(expected));
+            assert!(tree.distance_lower_bound(&q).unwrap() <= expected);
+            assert!(tree.any_within(&q, expected));
+            assert!(!tree.any_within(&q, expected * 0.99));
+        }
+    }
+
+    #[test]
+    fn empty_tree() {
+        let tree = SphereTree::from_points(&[]);
+        assert!(tree.is_empty());
+        assert_eq!(tree.nearest_distance(&v3!(0.0, 0.0, 0.0)), None);
+    }
+}
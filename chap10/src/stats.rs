@@ -0,0 +1,273 @@
+use crate::{v3, vector::V3};
+
+pub fn mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    Some(values.iter().sum::<f64>() / values.len() as f64)
+}
+
+// 母分散
+pub fn variance(values: &[f64]) -> Option<f64> {
+    let m = mean(values)?;
+
+    Some(values.iter().map(|v| (v - m) * (v - m)).sum::<f64>() / values.len() as f64)
+}
+
+pub fn min_max(values: &[f64]) -> Option<(f64, f64)> {
+    let first = *values.first()?;
+
+    Some(
+        values
+            .iter()
+            .fold((first, first), |(lo, hi), v| (lo.min(*v), hi.max(*v))),
+    )
+}
+
+// p は [0, 100]。順序統計量の間は線形補間する
+pub fn percentile(values: &[f64], p: f64) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+
+    let rank = (p.clamp(0.0, 100.0) / 100.0) * (sorted.len() - 1) as f64;
+    let lo = rank.floor() as usize;
+    let hi = rank.ceil() as usize;
+    let t = rank - lo as f64;
+
+    Some(sorted[lo] + (sorted[hi] - sorted[lo]) * t)
+}
+
+pub fn mean_v3(points: &[V3]) -> Option<V3> {
+    if points.is_empty() {
+        return None;
+    }
+
+    let sum = points.iter().fold(v3!(0.0, 0.0, 0.0), |acc, p| acc + *p);
+    Some(sum / points.len() as f64)
+}
+
+// 軸ごとの最小値と最大値
+pub fn bounds(points: &[V3]) -> Option<(V3, V3)> {
+    let first = *points.first()?;
+
+    Some(
+        points
+            .iter()
+            .fold((first, first), |(lo, hi), p| (lo.min(p), hi.max(p))),
+    )
+}
+
+// 母共分散行列
+pub fn covariance(points: &[V3]) -> Option<[[f64; 3]; 3]> {
+    let m = mean_v3(points)?;
+
+    let mut result = [[0.0; 3]; 3];
+    for p in points {
+        let d = [p.x - m.x, p.y - m.y, p.z - m.z];
+        for (i, row) in result.iter_mut().enumerate() {
+            for (j, c) in row.iter_mut().enumerate() {
+                *c += d[i] * d[j];
+            }
+        }
+    }
+
+    let n = points.len() as f64;
+    for c in result.iter_mut().flatten() {
+        *c /= n;
+    }
+
+    Some(result)
+}
+
+// 値の範囲を等幅のビンに分けた度数分布
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub min: f64,
+    pub max: f64,
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    // 値の最小値から最大値までをbins個に分ける
+    pub fn new(values: &[f64], bins: usize) -> Option<Self> {
+        let (min, max) = min_max(values)?;
+        Self::with_range(values, bins, min, max)
+    }
+
+    // [min, max] をbins個に分ける。範囲外やNaNの値は数えない
+    pub fn with_range(values: &[f64], bins: usize, min: f64, max: f64) -> Option<Self> {
+        if bins == 0 || !min.is_finite() || !max.is_finite() || min > max {
+            return None;
+        }
+
+        let mut result = Self {
+            min,
+            max,
+            counts: vec![0; bins],
+        };
+        for v in values {
+            if let Some(i) = result.bin_index(*v) {
+                result.counts[i] += 1;
+            }
+        }
+
+        Some(result)
+    }
+
+    pub fn bin_width(&self) -> f64 {
+        (self.max - self.min) / self.counts.len() as f64
+    }
+
+    // 値が入るビン。最大値ちょうどは最後のビンに含める
+    pub fn bin_index(&self, value: f64) -> Option<usize> {
+        if !(value >= self.min && value <= self.max) {
+            return None;
+        }
+        if self.max == self.min {
+            return Some(0);
+        }
+
+        let bins = self.counts.len();
+        let i = ((value - self.min) / (self.max - self.min) * bins as f64) as usize;
+        Some(i.min(bins - 1))
+    }
+
+    // i番目のビンの [下端, 上端)
+    pub fn bin_range(&self, i: usize) -> (f64, f64) {
+        let w = self.bin_width();
+        (self.min + w * i as f64, self.min + w * (i + 1) as f64)
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+}
+
+// 対称行列の固有値と固有ベクトル(列)をヤコビ法で求める
+fn symmetric_eigen(mut a: [[f64; 3]; 3]) -> ([f64; 3], [[f64; 3]; 3]) {
+    let mut v = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+    for _ in 0..32 {
+        let off = a[0][1] * a[0][1] + a[0][2] * a[0][2] + a[1][2] * a[1][2];
+        let diag = a[0][0] * a[0][0] + a[1][1] * a[1][1] + a[2][2] * a[2][2];
+        if off <= diag * 1e-30 {
+            break;
+        }
+
+        for (p, q) in [(0, 1), (0, 2), (1, 2)] {
+            if a[p][q] == 0.0 {
+                continue;
+            }
+
+            // a[p][q] を0にする回転角のtanを小さい方の解で求める
+            let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+            let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+            let c = 1.0 / (t * t + 1.0).sqrt();
+            let s = t * c;
+
+            for row in a.iter_mut() {
+                let (kp, kq) = (row[p], row[q]);
+                row[p] = c * kp - s * kq;
+                row[q] = s * kp + c * kq;
+            }
+            let (rp, rq) = (a[p], a[q]);
+            a[p] = [0, 1, 2].map(|k| c * rp[k] - s * rq[k]);
+            a[q] = [0, 1, 2].map(|k| s * rp[k] + c * rq[k]);
+            for row in v.iter_mut() {
+                let (kp, kq) = (row[p], row[q]);
+                row[p] = c * kp - s * kq;
+                row[q] = s * kp + c * kq;
+            }
+        }
+    }
+
+    ([a[0][0], a[1][1], a[2][2]], v)
+}
+
+// 共分散行列の最大固有値に対応する単位ベクトル
+// べき乗法は初期値が支配的でない固有ベクトルに一致すると抜け出せないのでヤコビ法を使う
+// 点が1点に集まっていて方向が決まらない場合はNone
+pub fn principal_axis(points: &[V3]) -> Option<V3> {
+    let (values, vectors) = symmetric_eigen(covariance(points)?);
+
+    let i = (0..3).max_by(|a, b| values[*a].total_cmp(&values[*b]))?;
+    if values[i] <= 0.0 {
+        return None;
+    }
+
+    Some(v3!(vectors[0][i], vectors[1][i], vectors[2][i]).normalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scalar_summary() {
+        let values = [4.0, 1.0, 3.0, 2.0];
+        assert_eq!(mean(&values), Some(2.5));
+        assert_eq!(variance(&values), Some(1.25));
+        assert_eq!(min_max(&values), Some((1.0, 4.0)));
+        assert_eq!(percentile(&values, 50.0), Some(2.5));
+        assert_eq!(percentile(&values, 100.0), Some(4.0));
+        assert_eq!(mean(&[]), None);
+    }
+
+    #[test]
+    fn principal_axis_of_line() {
+        let points: Vec<V3> = (0..5).map(|i| v3!(i as f64, 2.0 * i as f64, 0.0)).collect();
+        let axis = principal_axis(&points).unwrap();
+        let expected = v3!(1.0, 2.0, 0.0).normalize();
+        assert!((axis - expected).mag() < 1e-9 || (axis + expected).mag() < 1e-9);
+    }
+
+    #[test]
+    fn principal_axis_not_fooled_by_largest_diagonal() {
+        // 分散はz軸方向が最大だが、主軸は(1, 1, 0)方向
+        let points = [
+            v3!(1.0, 1.0, 0.0),
+            v3!(-1.0, -1.0, 0.0),
+            v3!(0.0, 0.0, 1.2),
+            v3!(0.0, 0.0, -1.2),
+        ];
+        let axis = principal_axis(&points).unwrap();
+        let expected = v3!(1.0, 1.0, 0.0).normalize();
+        assert!((axis - expected).mag() < 1e-9 || (axis + expected).mag() < 1e-9);
+
+        assert_eq!(principal_axis(&[v3!(1.0, 2.0, 3.0); 3]), None);
+    }
+
+    #[test]
+    fn histogram_counts() {
+        let values = [0.0, 0.5, 1.0, 2.5, 3.9, 4.0, f64::NAN];
+        let h = Histogram::new(&values[..6], 4).unwrap();
+        assert_eq!(h.counts, vec![2, 1, 1, 2]);
+        assert_eq!(h.bin_width(), 1.0);
+        assert_eq!(h.bin_range(2), (2.0, 3.0));
+
+        let h = Histogram::with_range(&values, 2, 1.0, 3.0).unwrap();
+        assert_eq!(h.counts, vec![1, 1]);
+        assert_eq!(h.total(), 2);
+
+        assert_eq!(
+            Histogram::new(&[2.0, 2.0], 3).unwrap().counts,
+            vec![2, 0, 0]
+        );
+        assert_eq!(Histogram::new(&values, 0), None);
+        assert_eq!(Histogram::new(&[], 4), None);
+    }
+
+    #[test]
+    fn bounds_of_points() {
+        let points = [v3!(1.0, -2.0, 3.0), v3!(-1.0, 2.0, 0.0)];
+        assert_eq!(
+            bounds(&points),
+            Some((v3!(-1.0, -2.0, 0.0), v3!(1.0, 2.0, 3.0)))
+        );
+    }
+}
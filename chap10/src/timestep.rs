@@ -0,0 +1,139 @@
+use crate::{matrix::Matrix3x4, se3::Se3};
+
+// 固定刻みでシミュレーションを進め、描画用の補間係数を返す
+pub struct FixedTimestep {
+    dt: f64,
+    accumulator: f64,
+    // 処理落ちで更新が追いつかなくなるのを防ぐ、1フレームあたりの最大ステップ数
+    max_steps: usize,
+}
+
+impl FixedTimestep {
+    pub fn new(dt: f64) -> Self {
+        assert!(dt > 0.0);
+
+        Self {
+            dt,
+            accumulator: 0.0,
+            max_steps: 8,
+        }
+    }
+
+    // max_stepsが0だと一度もstepを呼ばなくなるので認めない
+    pub fn with_max_steps(dt: f64, max_steps: usize) -> Self {
+        assert!(max_steps > 0);
+
+        Self {
+            max_steps,
+            ..Self::new(dt)
+        }
+    }
+
+    pub fn dt(&self) -> f64 {
+        self.dt
+    }
+
+    // 前回の状態と現在の状態の間の補間係数 [0, 1)
+    pub fn alpha(&self) -> f64 {
+        self.accumulator / self.dt
+    }
+
+    // フレームの経過時間を加え、溜まった分だけstepを呼ぶ。戻り値は補間係数
+    pub fn advance<F>(&mut self, frame_time: f64, mut step: F) -> f64
+    where
+        F: FnMut(f64),
+    {
+        self.accumulator += frame_time.max(0.0);
+
+        let mut steps = 0;
+        while self.accumulator >= self.dt {
+            if steps == self.max_steps {
+                // 追いつけない分は捨てる
+                self.accumulator %= self.dt;
+                break;
+            }
+            step(self.dt);
+            self.accumulator -= self.dt;
+            steps += 1;
+        }
+
+        self.alpha()
+    }
+}
+
+// 描画用に、前回と現在の剛体姿勢をadvanceが返したalphaで補間する
+// 1ステップ分の差を回転と並進をまとめたねじり運動で結ぶので、一定速度の運動はそのまま再現される
+pub fn blend(prev: &Se3, curr: &Se3, alpha: f64) -> Se3 {
+    prev.screw_interpolate(curr, alpha)
+}
+
+// 状態を行列で持つ場合。スケールを含んでいてもよい
+pub fn blend_matrix(prev: &Matrix3x4, curr: &Matrix3x4, alpha: f64) -> Matrix3x4 {
+    prev.interpolate(curr, alpha)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{approx::ApproxEq, se3::Twist, v3, vector::V3};
+
+    #[test]
+    fn accumulates_fixed_steps() {
+        let mut timestep = FixedTimestep::new(0.25);
+        let mut steps = 0;
+
+        let alpha = timestep.advance(0.6, |_| steps += 1);
+        assert_eq!(steps, 2);
+        assert!((alpha - 0.4).abs() < 1e-12);
+
+        timestep.advance(0.2, |_| steps += 1);
+        assert_eq!(steps, 3);
+    }
+
+    #[test]
+    fn clamps_steps_per_frame() {
+        let mut timestep = FixedTimestep::with_max_steps(0.1, 3);
+        let mut steps = 0;
+
+        timestep.advance(10.0, |_| steps += 1);
+        assert_eq!(steps, 3);
+        assert!(timestep.alpha() < 1.0);
+    }
+
+    #[test]
+    fn blends_render_state() {
+        let twist = Twist::new(v3!(0.0, 0.2, 0.0), v3!(1.0, 0.0, 0.0));
+        let velocity = Se3::exp(twist);
+
+        let mut timestep = FixedTimestep::new(0.25);
+        let mut prev = Se3::identity();
+        let mut curr = Se3::identity();
+        let alpha = timestep.advance(0.6, |_| {
+            prev = curr;
+            curr *= velocity;
+        });
+
+        // 2ステップ進んだので、描画するのは1 + alpha ステップ目の姿勢
+        let expected = Se3::exp(Twist::new(
+            twist.angular * (1.0 + alpha),
+            twist.linear * (1.0 + alpha),
+        ));
+        let blended = blend(&prev, &curr, alpha);
+        assert!(blended
+            .rotation
+            .approx_eq_rotation(&expected.rotation, 1e-9));
+        assert!(blended.translation.abs_diff_eq(&expected.translation, 1e-9));
+
+        let a = Matrix3x4::from_translation(v3!(0.0, 0.0, 0.0));
+        let b = Matrix3x4::from_translation(v3!(2.0, -4.0, 1.0));
+        assert!(blend_matrix(&a, &b, alpha)
+            .get_translation()
+            .abs_diff_eq(&v3!(0.8, -1.6, 0.4), 1e-9));
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_max_steps_rejected() {
+        FixedTimestep::with_max_steps(0.1, 0);
+    }
+}
@@ -0,0 +1,147 @@
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+// 同じ単位同士の加減算とスカラー倍だけを許す
+macro_rules! unit {
+    ($name:ident) => {
+        #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+        pub struct $name(pub f64);
+
+        impl $name {
+            pub fn value(self) -> f64 {
+                self.0
+            }
+        }
+
+        impl From<$name> for f64 {
+            fn from(v: $name) -> f64 {
+                v.0
+            }
+        }
+
+        impl Add for $name {
+            type Output = $name;
+
+            fn add(self, rhs: Self) -> Self::Output {
+                $name(self.0 + rhs.0)
+            }
+        }
+
+        impl AddAssign for $name {
+            fn add_assign(&mut self, rhs: Self) {
+                self.0 += rhs.0;
+            }
+        }
+
+        impl Sub for $name {
+            type Output = $name;
+
+            fn sub(self, rhs: Self) -> Self::Output {
+                $name(self.0 - rhs.0)
+            }
+        }
+
+        impl SubAssign for $name {
+            fn sub_assign(&mut self, rhs: Self) {
+                self.0 -= rhs.0;
+            }
+        }
+
+        impl Neg for $name {
+            type Output = $name;
+
+            fn neg(self) -> Self::Output {
+                $name(-self.0)
+            }
+        }
+
+        impl Mul<f64> for $name {
+            type Output = $name;
+
+            fn mul(self, rhs: f64) -> Self::Output {
+                $name(self.0 * rhs)
+            }
+        }
+
+        impl Mul<$name> for f64 {
+            type Output = $name;
+
+            fn mul(self, rhs: $name) -> Self::Output {
+                $name(self * rhs.0)
+            }
+        }
+
+        impl Div<f64> for $name {
+            type Output = $name;
+
+            fn div(self, rhs: f64) -> Self::Output {
+                $name(self.0 / rhs)
+            }
+        }
+
+        // 同じ単位同士の比は無次元
+        impl Div for $name {
+            type Output = f64;
+
+            fn div(self, rhs: Self) -> Self::Output {
+                self.0 / rhs.0
+            }
+        }
+    };
+}
+
+unit!(Meters);
+unit!(Seconds);
+unit!(MetersPerSecond);
+
+impl Div<Seconds> for Meters {
+    type Output = MetersPerSecond;
+
+    fn div(self, rhs: Seconds) -> Self::Output {
+        MetersPerSecond(self.0 / rhs.0)
+    }
+}
+
+impl Mul<Seconds> for MetersPerSecond {
+    type Output = Meters;
+
+    fn mul(self, rhs: Seconds) -> Self::Output {
+        Meters(self.0 * rhs.0)
+    }
+}
+
+impl Mul<MetersPerSecond> for Seconds {
+    type Output = Meters;
+
+    fn mul(self, rhs: MetersPerSecond) -> Self::Output {
+        Meters(self.0 * rhs.0)
+    }
+}
+
+impl Div<MetersPerSecond> for Meters {
+    type Output = Seconds;
+
+    fn div(self, rhs: MetersPerSecond) -> Self::Output {
+        Seconds(self.0 / rhs.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speed_from_distance_and_time() {
+        let speed = Meters(10.0) / Seconds(4.0);
+        assert_eq!(speed, MetersPerSecond(2.5));
+        assert_eq!(speed * Seconds(2.0), Meters(5.0));
+        assert_eq!(Meters(5.0) / speed, Seconds(2.0));
+    }
+
+    #[test]
+    fn same_unit_arithmetic() {
+        let mut d = Meters(1.0) + Meters(2.0) * 2.0;
+        d -= Meters(1.0);
+        assert_eq!(f64::from(d), 4.0);
+        assert_eq!(d / Meters(2.0), 2.0);
+    }
+}
@@ -5,6 +5,15 @@ pub const PI_OVER_2: f64 = PI / 2.0;
 pub const ONE_OVER_PI: f64 = 1.0 / PI;
 pub const ONE_OVER_2PI: f64 = 1.0 / PI2;
 
+// 値がNaNや無限大でないことをデバッグビルドでだけ確かめる。is_finite()を持つ型に使える
+// NaNは黙って伝搬するので、変換の入口で止めて原因を追いやすくする
+#[macro_export]
+macro_rules! debug_assert_finite {
+    ($value:expr $(,)?) => {
+        debug_assert!($value.is_finite(), "{} is not finite", stringify!($value))
+    };
+}
+
 pub trait GameMath {
     fn wrap_pi(self) -> Self;
     fn safe_acos(self) -> Self;
@@ -14,7 +23,7 @@ impl GameMath for f64 {
     fn wrap_pi(self) -> Self {
         let mut result = self;
         result += PI;
-        result -= (self * ONE_OVER_2PI).floor() * PI2;
+        result -= (result * ONE_OVER_2PI).floor() * PI2;
         result -= PI;
         result
     }
@@ -27,6 +36,6 @@ impl GameMath for f64 {
             return 0.0;
         }
 
-        return self.acos();
+        self.acos()
     }
 }
@@ -1,4 +1,12 @@
-use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use std::f64::consts::PI;
+use std::fmt;
+use std::num::ParseFloatError;
+use std::ops::{
+    Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign,
+};
+use std::str::FromStr;
+
+use crate::utils::GameMath;
 
 #[macro_export]
 macro_rules! v3 {
@@ -20,11 +28,43 @@ const ZERO: V3 = V3 {
     z: 0.0,
 };
 
+// スカラー三重積 a・(b×c)。3つのベクトルが張る平行六面体の符号付き体積
+pub fn triple_product(a: &V3, b: &V3, c: &V3) -> f64 {
+    a.dot(&b.cross(c))
+}
+
+// 各成分を格子に丸めたハッシュ可能なキー。頂点の溶接や空間ハッシュに使う
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct QuantizedV3 {
+    pub x: i64,
+    pub y: i64,
+    pub z: i64,
+}
+
+impl QuantizedV3 {
+    // 格子点の座標に戻す
+    pub fn to_v3(&self, epsilon: f64) -> V3 {
+        V3::new(
+            self.x as f64 * epsilon,
+            self.y as f64 * epsilon,
+            self.z as f64 * epsilon,
+        )
+    }
+}
+
 impl V3 {
     pub fn new(x: f64, y: f64, z: f64) -> Self {
         V3 { x, y, z }
     }
 
+    // 長さがちょうど3のときだけ変換する
+    pub fn from_slice(values: &[f64]) -> Option<Self> {
+        match values {
+            [x, y, z] => Some(v3!(*x, *y, *z)),
+            _ => None,
+        }
+    }
+
     pub fn zero(&mut self) {
         self.x = 0.0;
         self.y = 0.0;
@@ -47,13 +87,94 @@ impl V3 {
         (self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
     }
 
+    // 比較だけならsqrtは不要
+    pub fn mag_squared(&self) -> f64 {
+        self.x * self.x + self.y * self.y + self.z * self.z
+    }
+
     pub fn normalize(&self) -> Self {
         let mag = self.mag();
 
         *self / mag
     }
 
-    pub fn dot(&self, rhs: &Self) -> Self {
+    // ゼロベクトル(や長さが小さすぎて逆数が無限大になるもの)はNone
+    pub fn try_normalize(&self) -> Option<Self> {
+        let one_over_mag = 1.0 / self.mag();
+        if one_over_mag.is_finite() && one_over_mag > 0.0 {
+            Some(*self * one_over_mag)
+        } else {
+            None
+        }
+    }
+
+    pub fn normalize_or(&self, fallback: Self) -> Self {
+        self.try_normalize().unwrap_or(fallback)
+    }
+
+    pub fn normalize_or_zero(&self) -> Self {
+        self.normalize_or(ZERO)
+    }
+
+    // 長さをmax_len以下に抑える。範囲内ならsqrtを使わない
+    pub fn clamp_magnitude(&self, max_len: f64) -> Self {
+        let mag_sq = self.mag_squared();
+        if mag_sq <= max_len * max_len {
+            return *self;
+        }
+
+        *self * (max_len / mag_sq.sqrt())
+    }
+
+    // targetに向かって最大max_deltaだけ進む。届くならtargetそのもの
+    pub fn move_towards(&self, target: &Self, max_delta: f64) -> Self {
+        let delta = *target - *self;
+        let dist_sq = delta.mag_squared();
+        if dist_sq <= max_delta * max_delta {
+            return *target;
+        }
+
+        *self + delta * (max_delta / dist_sq.sqrt())
+    }
+
+    // 単位ベクトルnに直交する (tangent, bitangent) の組。tangent × bitangent = n になる
+    // 分岐なしで特異点のない Duff et al. 2017 の方法
+    pub fn any_orthonormal_basis(&self) -> (Self, Self) {
+        let sign = 1.0_f64.copysign(self.z);
+        let a = -1.0 / (sign + self.z);
+        let b = self.x * self.y * a;
+
+        (
+            V3::new(1.0 + sign * self.x * self.x * a, sign * b, -sign * self.x),
+            V3::new(b, sign + self.y * self.y * a, -self.y),
+        )
+    }
+
+    // 間隔epsilonの格子の最も近い点に丸める
+    pub fn quantize(&self, epsilon: f64) -> QuantizedV3 {
+        let quantize = |c: f64| (c / epsilon).round() as i64;
+
+        QuantizedV3 {
+            x: quantize(self.x),
+            y: quantize(self.y),
+            z: quantize(self.z),
+        }
+    }
+
+    pub fn is_finite(&self) -> bool {
+        self.x.is_finite() && self.y.is_finite() && self.z.is_finite()
+    }
+
+    pub fn is_nan(&self) -> bool {
+        self.x.is_nan() || self.y.is_nan() || self.z.is_nan()
+    }
+
+    pub fn dot(&self, rhs: &Self) -> f64 {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
+    }
+
+    // 成分ごとの積
+    pub fn component_mul(&self, rhs: &Self) -> Self {
         V3 {
             x: self.x * rhs.x,
             y: self.y * rhs.y,
@@ -69,9 +190,150 @@ impl V3 {
         }
     }
 
+    // 法線nの面で反射したベクトル。nは単位ベクトル
+    pub fn reflect(&self, normal: &Self) -> Self {
+        *self - *normal * (2.0 * self.dot(normal))
+    }
+
+    // 屈折したベクトル。etaは屈折率の比(入射側 / 透過側)
+    // selfとnormalは単位ベクトル、normalは入射側を向く。全反射の場合はNone
+    pub fn refract(&self, normal: &Self, eta: f64) -> Option<Self> {
+        let cos_i = self.dot(normal);
+        let k = 1.0 - eta * eta * (1.0 - cos_i * cos_i);
+        if k < 0.0 {
+            return None;
+        }
+
+        Some(*self * eta - *normal * (eta * cos_i + k.sqrt()))
+    }
+
+    // otherへの射影 (a・b / b・b) * b。otherがゼロベクトルならゼロ
+    pub fn project_onto(&self, other: &Self) -> Self {
+        let len_sq = other.mag_squared();
+        if len_sq == 0.0 {
+            return ZERO;
+        }
+
+        *other * (self.dot(other) / len_sq)
+    }
+
+    // otherに垂直な成分
+    pub fn reject_from(&self, other: &Self) -> Self {
+        *self - self.project_onto(other)
+    }
+
+    // 法線normalの平面への射影
+    pub fn project_onto_plane(&self, normal: &Self) -> Self {
+        self.reject_from(normal)
+    }
+
+    // 2つのベクトルのなす角 [0, PI]。丸め誤差で内積が±1を超えてもNaNにならない
+    pub fn angle_between(&self, other: &Self) -> f64 {
+        let mags = self.mag() * other.mag();
+        if mags == 0.0 {
+            return 0.0;
+        }
+
+        (self.dot(other) / mags).safe_acos()
+    }
+
+    // axisまわりに見た符号付きの角度 [-PI, PI]
+    pub fn signed_angle_between(&self, other: &Self, axis: &Self) -> f64 {
+        let angle = self.angle_between(other);
+
+        if self.cross(other).dot(axis) < 0.0 {
+            -angle
+        } else {
+            angle
+        }
+    }
+
+    // 成分ごとの最小値
+    pub fn min(&self, other: &Self) -> Self {
+        V3 {
+            x: self.x.min(other.x),
+            y: self.y.min(other.y),
+            z: self.z.min(other.z),
+        }
+    }
+
+    // 成分ごとの最大値
+    pub fn max(&self, other: &Self) -> Self {
+        V3 {
+            x: self.x.max(other.x),
+            y: self.y.max(other.y),
+            z: self.z.max(other.z),
+        }
+    }
+
+    // 成分ごとに [lo, hi] に収める
+    pub fn clamp(&self, lo: &Self, hi: &Self) -> Self {
+        self.max(lo).min(hi)
+    }
+
+    pub fn abs(&self) -> Self {
+        V3 {
+            x: self.x.abs(),
+            y: self.y.abs(),
+            z: self.z.abs(),
+        }
+    }
+
+    pub fn signum(&self) -> Self {
+        V3 {
+            x: self.x.signum(),
+            y: self.y.signum(),
+            z: self.z.signum(),
+        }
+    }
+
+    pub fn min_element(&self) -> f64 {
+        self.x.min(self.y).min(self.z)
+    }
+
+    pub fn max_element(&self) -> f64 {
+        self.x.max(self.y).max(self.z)
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.x + self.y + self.z
+    }
+
+    pub fn product(&self) -> f64 {
+        self.x * self.y * self.z
+    }
+
     pub fn distance(&self, rhs: &Self) -> f64 {
         (*self - *rhs).mag()
     }
+
+    pub fn distance_squared(&self, rhs: &Self) -> f64 {
+        (*self - *rhs).mag_squared()
+    }
+
+    // 単位ベクトル同士の球面線形補間
+    pub fn slerp(&self, rhs: &Self, t: f64) -> Self {
+        let cos_omega = self.dot(rhs);
+        let sin_omega = self.cross(rhs).mag();
+
+        if sin_omega < 1e-9 {
+            // ほぼ同じ向きなら線形補間で十分
+            if cos_omega > 0.0 {
+                return (*self + (*rhs - *self) * t).normalize();
+            }
+
+            // 真逆の向きは大円が決まらないので、直交する任意の軸まわりに半回転させる
+            let (perp, _) = self.normalize().any_orthonormal_basis();
+            let (sin_t, cos_t) = (t * PI).sin_cos();
+            return *self * cos_t + perp * (self.mag() * sin_t);
+        }
+
+        let omega = sin_omega.atan2(cos_omega);
+        let k0 = ((1.0 - t) * omega).sin() / sin_omega;
+        let k1 = (t * omega).sin() / sin_omega;
+
+        *self * k0 + *rhs * k1
+    }
 }
 
 impl Add for V3 {
@@ -178,6 +440,131 @@ impl Mul<V3> for f64 {
     }
 }
 
+// 0: x, 1: y, 2: z。範囲外は配列と同じくpanicする
+impl Index<usize> for V3 {
+    type Output = f64;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        match index {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!(
+                "index out of bounds: the len is 3 but the index is {}",
+                index
+            ),
+        }
+    }
+}
+
+impl IndexMut<usize> for V3 {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        match index {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            2 => &mut self.z,
+            _ => panic!(
+                "index out of bounds: the len is 3 but the index is {}",
+                index
+            ),
+        }
+    }
+}
+
+impl From<[f64; 3]> for V3 {
+    fn from([x, y, z]: [f64; 3]) -> Self {
+        v3!(x, y, z)
+    }
+}
+
+impl From<V3> for [f64; 3] {
+    fn from(v: V3) -> Self {
+        [v.x, v.y, v.z]
+    }
+}
+
+impl From<(f64, f64, f64)> for V3 {
+    fn from((x, y, z): (f64, f64, f64)) -> Self {
+        v3!(x, y, z)
+    }
+}
+
+impl From<V3> for (f64, f64, f64) {
+    fn from(v: V3) -> Self {
+        (v.x, v.y, v.z)
+    }
+}
+
+impl IntoIterator for V3 {
+    type Item = f64;
+    type IntoIter = std::array::IntoIter<f64, 3>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        [self.x, self.y, self.z].into_iter()
+    }
+}
+
+// 先頭から3つを使う。足りない成分は0、余った要素は捨てる
+impl FromIterator<f64> for V3 {
+    fn from_iter<I: IntoIterator<Item = f64>>(iter: I) -> Self {
+        let mut result = ZERO;
+        for (i, value) in iter.into_iter().take(3).enumerate() {
+            result[i] = value;
+        }
+
+        result
+    }
+}
+
+// (1.0, 2.0, 3.0) の形式。{:.3} のように精度を指定するとそれに従う
+impl fmt::Display for V3 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match f.precision() {
+            Some(p) => write!(f, "({:.*}, {:.*}, {:.*})", p, self.x, p, self.y, p, self.z),
+            None => write!(f, "({:?}, {:?}, {:?})", self.x, self.y, self.z),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseV3Error {
+    // 成分の数が3ではない
+    WrongCount(usize),
+    Float(ParseFloatError),
+}
+
+impl fmt::Display for ParseV3Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseV3Error::WrongCount(n) => write!(f, "expected 3 components, found {}", n),
+            ParseV3Error::Float(e) => write!(f, "invalid component: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ParseV3Error {}
+
+// "(1, 2, 3)" と "1, 2, 3" のどちらも受け付ける。Displayの出力はそのまま読み戻せる
+impl FromStr for V3 {
+    type Err = ParseV3Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s
+            .strip_prefix('(')
+            .and_then(|s| s.strip_suffix(')'))
+            .unwrap_or(s);
+
+        let values = s
+            .split(',')
+            .map(|c| c.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(ParseV3Error::Float)?;
+
+        V3::from_slice(&values).ok_or(ParseV3Error::WrongCount(values.len()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -249,11 +636,74 @@ mod tests {
         assert_eq!(a.mag(), 2.0);
     }
 
+    #[test]
+    fn mag_squared() {
+        let a = v3!(1.0, 2.0, 2.0);
+        assert_eq!(a.mag_squared(), 9.0);
+    }
+
+    #[test]
+    fn try_normalize() {
+        let zero = v3!(0.0, 0.0, 0.0);
+        let x = v3!(1.0, 0.0, 0.0);
+        assert_eq!(v3!(3.0, 0.0, 0.0).try_normalize(), Some(x));
+        assert_eq!(zero.try_normalize(), None);
+        assert_eq!(zero.normalize_or(x), x);
+        assert_eq!(zero.normalize_or_zero(), zero);
+        assert_eq!(v3!(0.0, -2.0, 0.0).normalize_or_zero(), v3!(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn clamp_magnitude() {
+        let a = v3!(3.0, 0.0, 4.0);
+        assert_eq!(a.clamp_magnitude(10.0), a);
+        assert_eq!(a.clamp_magnitude(2.5), v3!(1.5, 0.0, 2.0));
+    }
+
+    #[test]
+    fn move_towards() {
+        let a = v3!(0.0, 0.0, 0.0);
+        let b = v3!(0.0, 4.0, 0.0);
+        assert_eq!(a.move_towards(&b, 1.0), v3!(0.0, 1.0, 0.0));
+        assert_eq!(a.move_towards(&b, 5.0), b);
+    }
+
+    #[test]
+    fn triple_product() {
+        let x = v3!(1.0, 0.0, 0.0);
+        let y = v3!(0.0, 1.0, 0.0);
+        let z = v3!(0.0, 0.0, 2.0);
+        assert_eq!(super::triple_product(&x, &y, &z), 2.0);
+        assert_eq!(super::triple_product(&y, &x, &z), -2.0);
+    }
+
+    #[test]
+    fn any_orthonormal_basis() {
+        for n in [
+            v3!(0.0, 0.0, 1.0),
+            v3!(0.0, 0.0, -1.0),
+            v3!(1.0, 2.0, -3.0).normalize(),
+        ] {
+            let (t, b) = n.any_orthonormal_basis();
+            assert!((t.mag() - 1.0).abs() < 1e-12);
+            assert!((b.mag() - 1.0).abs() < 1e-12);
+            assert!(t.dot(&n).abs() < 1e-12 && b.dot(&n).abs() < 1e-12);
+            assert!((t.cross(&b) - n).mag() < 1e-12);
+        }
+    }
+
     #[test]
     fn dot() {
         let a = v3!(1.0, 2.0, 3.0);
         let b = v3!(1.0, 2.0, 3.0);
-        assert_eq!(a.dot(&b), v3!(1.0, 4.0, 9.0));
+        assert_eq!(a.dot(&b), 14.0);
+    }
+
+    #[test]
+    fn component_mul() {
+        let a = v3!(1.0, 2.0, 3.0);
+        let b = v3!(1.0, 2.0, 3.0);
+        assert_eq!(a.component_mul(&b), v3!(1.0, 4.0, 9.0));
     }
 
     #[test]
@@ -263,10 +713,198 @@ mod tests {
         assert_eq!(a.cross(&b), v3!(-4.0, 8.0, -4.0));
     }
 
+    #[test]
+    fn slerp() {
+        let a = v3!(1.0, 0.0, 0.0);
+        let b = v3!(0.0, 1.0, 0.0);
+        let half = 0.5f64.sqrt();
+        assert!((a.slerp(&b, 0.5) - v3!(half, half, 0.0)).mag() < 1e-12);
+        assert_eq!(a.slerp(&a, 0.3), a);
+
+        // 真逆の向きでもNaNにならず、大円上を半周する
+        let c = v3!(0.0, 0.0, -1.0);
+        let d = v3!(0.0, 0.0, 1.0);
+        for t in [0.0, 0.25, 0.5, 1.0] {
+            let p = c.slerp(&d, t);
+            assert!(p.is_finite());
+            assert!((p.mag() - 1.0).abs() < 1e-12);
+            assert!((p.dot(&c) - (t * PI).cos()).abs() < 1e-12);
+        }
+        assert!((c.slerp(&d, 1.0) - d).mag() < 1e-12);
+    }
+
+    #[test]
+    fn reflect() {
+        let a = v3!(1.0, -1.0, 0.0);
+        let n = v3!(0.0, 1.0, 0.0);
+        assert_eq!(a.reflect(&n), v3!(1.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn refract() {
+        let n = v3!(0.0, 1.0, 0.0);
+        let straight = v3!(0.0, -1.0, 0.0);
+        assert_eq!(straight.refract(&n, 1.5), Some(straight));
+
+        let grazing = v3!(1.0, -0.1, 0.0).normalize();
+        assert_eq!(grazing.refract(&n, 1.5), None);
+    }
+
+    #[test]
+    fn project_onto() {
+        let a = v3!(2.0, 3.0, 0.0);
+        let b = v3!(4.0, 0.0, 0.0);
+        assert_eq!(a.project_onto(&b), v3!(2.0, 0.0, 0.0));
+        assert_eq!(a.project_onto(&v3!(0.0, 0.0, 0.0)), v3!(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn reject_from() {
+        let a = v3!(2.0, 3.0, 0.0);
+        let b = v3!(4.0, 0.0, 0.0);
+        assert_eq!(a.reject_from(&b), v3!(0.0, 3.0, 0.0));
+        assert_eq!(
+            a.project_onto_plane(&v3!(0.0, 1.0, 0.0)),
+            v3!(2.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn angle_between() {
+        let a = v3!(1.0, 0.0, 0.0);
+        assert_eq!(
+            a.angle_between(&v3!(0.0, 2.0, 0.0)),
+            std::f64::consts::FRAC_PI_2
+        );
+        assert_eq!(a.angle_between(&(a * 3.0)), 0.0);
+        assert!(!v3!(0.1, 0.2, 0.3)
+            .angle_between(&v3!(0.1, 0.2, 0.3))
+            .is_nan());
+    }
+
+    #[test]
+    fn signed_angle_between() {
+        let a = v3!(1.0, 0.0, 0.0);
+        let b = v3!(0.0, 1.0, 0.0);
+        let up = v3!(0.0, 0.0, 1.0);
+        assert_eq!(a.signed_angle_between(&b, &up), std::f64::consts::FRAC_PI_2);
+        assert_eq!(
+            b.signed_angle_between(&a, &up),
+            -std::f64::consts::FRAC_PI_2
+        );
+    }
+
+    #[test]
+    fn min_max() {
+        let a = v3!(1.0, 5.0, -3.0);
+        let b = v3!(2.0, 4.0, -6.0);
+        assert_eq!(a.min(&b), v3!(1.0, 4.0, -6.0));
+        assert_eq!(a.max(&b), v3!(2.0, 5.0, -3.0));
+    }
+
+    #[test]
+    fn clamp() {
+        let a = v3!(-1.0, 0.5, 3.0);
+        let lo = v3!(0.0, 0.0, 0.0);
+        let hi = v3!(1.0, 1.0, 1.0);
+        assert_eq!(a.clamp(&lo, &hi), v3!(0.0, 0.5, 1.0));
+    }
+
+    #[test]
+    fn abs_signum() {
+        let a = v3!(-1.0, 2.0, -3.0);
+        assert_eq!(a.abs(), v3!(1.0, 2.0, 3.0));
+        assert_eq!(a.signum(), v3!(-1.0, 1.0, -1.0));
+    }
+
+    #[test]
+    fn reductions() {
+        let a = v3!(2.0, -1.0, 3.0);
+        assert_eq!(a.min_element(), -1.0);
+        assert_eq!(a.max_element(), 3.0);
+        assert_eq!(a.sum(), 4.0);
+        assert_eq!(a.product(), -6.0);
+    }
+
+    #[test]
+    fn index() {
+        let mut a = v3!(1.0, 2.0, 3.0);
+        a[1] = 5.0;
+        assert_eq!((a[0], a[1], a[2]), (1.0, 5.0, 3.0));
+    }
+
+    #[test]
+    fn conversions() {
+        let a = v3!(1.0, 2.0, 3.0);
+        assert_eq!(V3::from([1.0, 2.0, 3.0]), a);
+        assert_eq!(V3::from((1.0, 2.0, 3.0)), a);
+        assert_eq!(<[f64; 3]>::from(a), [1.0, 2.0, 3.0]);
+        let t: (f64, f64, f64) = a.into();
+        assert_eq!(t, (1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn iterators() {
+        let a = v3!(1.0, 2.0, 3.0);
+        assert_eq!(a.into_iter().collect::<Vec<_>>(), vec![1.0, 2.0, 3.0]);
+        assert_eq!(a.into_iter().map(|c| c * 2.0).collect::<V3>(), a * 2.0);
+        assert_eq!([1.0].into_iter().collect::<V3>(), v3!(1.0, 0.0, 0.0));
+        assert_eq!(V3::from_slice(&[1.0, 2.0, 3.0]), Some(a));
+        assert_eq!(V3::from_slice(&[1.0, 2.0]), None);
+    }
+
+    #[test]
+    fn display() {
+        let a = v3!(1.0, -2.5, 3.0);
+        assert_eq!(a.to_string(), "(1.0, -2.5, 3.0)");
+        assert_eq!(format!("{:.2}", a), "(1.00, -2.50, 3.00)");
+    }
+
+    #[test]
+    fn from_str() {
+        let a = v3!(1.0, -2.5, 3.0);
+        assert_eq!("(1.0, -2.5, 3.0)".parse::<V3>(), Ok(a));
+        assert_eq!(" 1, -2.5,3 ".parse::<V3>(), Ok(a));
+        assert_eq!(a.to_string().parse::<V3>(), Ok(a));
+        assert_eq!("(1, 2)".parse::<V3>(), Err(ParseV3Error::WrongCount(2)));
+        assert!(matches!(
+            "(1, x, 3)".parse::<V3>(),
+            Err(ParseV3Error::Float(_))
+        ));
+    }
+
+    #[test]
+    fn quantize() {
+        use std::collections::HashSet;
+
+        let a = v3!(1.0, 2.0, -3.0);
+        let b = v3!(1.0 + 1e-7, 2.0, -3.0 - 1e-7);
+        let keys: HashSet<_> = [a, b].iter().map(|v| v.quantize(1e-4)).collect();
+        assert_eq!(keys.len(), 1);
+        assert_ne!(a.quantize(1e-4), v3!(1.001, 2.0, -3.0).quantize(1e-4));
+        assert_eq!(b.quantize(0.5).to_v3(0.5), a);
+    }
+
+    #[test]
+    fn is_finite() {
+        assert!(v3!(1.0, 2.0, 3.0).is_finite());
+        assert!(!v3!(1.0, f64::INFINITY, 3.0).is_finite());
+        assert!(!v3!(1.0, f64::NAN, 3.0).is_finite());
+        assert!(v3!(f64::NAN, 0.0, 0.0).is_nan());
+        assert!(!v3!(f64::INFINITY, 0.0, 0.0).is_nan());
+    }
+
     #[test]
     fn distance() {
         let a = v3!(1.0, 2.0, 3.0);
         let b = v3!(2.0, 2.0, 3.0);
         assert_eq!(a.distance(&b), 1.0);
     }
+
+    #[test]
+    fn distance_squared() {
+        let a = v3!(1.0, 2.0, 3.0);
+        let b = v3!(3.0, 2.0, 1.0);
+        assert_eq!(a.distance_squared(&b), 8.0);
+    }
 }
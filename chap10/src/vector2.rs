@@ -0,0 +1,194 @@
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+#[macro_export]
+macro_rules! v2 {
+    ($x:expr, $y:expr $(,)?) => {
+        V2::new($x, $y)
+    };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct V2 {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl V2 {
+    pub fn new(x: f64, y: f64) -> Self {
+        V2 { x, y }
+    }
+
+    pub fn zero(&mut self) {
+        self.x = 0.0;
+        self.y = 0.0;
+    }
+
+    pub fn mag(&self) -> f64 {
+        (self.x * self.x + self.y * self.y).sqrt()
+    }
+
+    pub fn mag_squared(&self) -> f64 {
+        self.x * self.x + self.y * self.y
+    }
+
+    pub fn normalize(&self) -> Self {
+        let mag = self.mag();
+
+        *self / mag
+    }
+
+    pub fn dot(&self, rhs: &Self) -> f64 {
+        self.x * rhs.x + self.y * rhs.y
+    }
+
+    // 2次元の外積 a.x * b.y - a.y * b.x。bがaの反時計回り側なら正
+    pub fn perp_dot(&self, rhs: &Self) -> f64 {
+        self.x * rhs.y - self.y * rhs.x
+    }
+
+    // 反時計回りに90度回転したベクトル
+    pub fn perp(&self) -> Self {
+        V2 {
+            x: -self.y,
+            y: self.x,
+        }
+    }
+
+    pub fn distance(&self, rhs: &Self) -> f64 {
+        (*self - *rhs).mag()
+    }
+
+    pub fn lerp(&self, rhs: &Self, t: f64) -> Self {
+        *self + (*rhs - *self) * t
+    }
+}
+
+impl Add for V2 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        V2 {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+        }
+    }
+}
+
+impl AddAssign for V2 {
+    fn add_assign(&mut self, rhs: Self) {
+        self.x += rhs.x;
+        self.y += rhs.y;
+    }
+}
+
+impl Sub for V2 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        V2 {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+        }
+    }
+}
+
+impl SubAssign for V2 {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.x -= rhs.x;
+        self.y -= rhs.y;
+    }
+}
+
+impl Neg for V2 {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        V2 {
+            x: -self.x,
+            y: -self.y,
+        }
+    }
+}
+
+impl Mul<f64> for V2 {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        V2 {
+            x: self.x * rhs,
+            y: self.y * rhs,
+        }
+    }
+}
+
+impl MulAssign<f64> for V2 {
+    fn mul_assign(&mut self, rhs: f64) {
+        self.x *= rhs;
+        self.y *= rhs;
+    }
+}
+
+impl Div<f64> for V2 {
+    type Output = Self;
+
+    fn div(self, rhs: f64) -> Self::Output {
+        V2 {
+            x: self.x / rhs,
+            y: self.y / rhs,
+        }
+    }
+}
+
+impl DivAssign<f64> for V2 {
+    fn div_assign(&mut self, rhs: f64) {
+        self.x /= rhs;
+        self.y /= rhs;
+    }
+}
+
+impl Mul<V2> for f64 {
+    type Output = V2;
+
+    fn mul(self, rhs: V2) -> Self::Output {
+        V2 {
+            x: self * rhs.x,
+            y: self * rhs.y,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        let a = v2!(1.0, 2.0);
+        let b = v2!(3.0, -1.0);
+        assert_eq!(a + b, v2!(4.0, 1.0));
+        assert_eq!(a - b, v2!(-2.0, 3.0));
+        assert_eq!(-a, v2!(-1.0, -2.0));
+        assert_eq!(a * 2.0, v2!(2.0, 4.0));
+        assert_eq!(2.0 * a, v2!(2.0, 4.0));
+        assert_eq!(b / 2.0, v2!(1.5, -0.5));
+    }
+
+    #[test]
+    fn dot_and_perp_dot() {
+        let a = v2!(1.0, 0.0);
+        let b = v2!(0.0, 2.0);
+        assert_eq!(a.dot(&b), 0.0);
+        assert_eq!(a.perp_dot(&b), 2.0);
+        assert_eq!(b.perp_dot(&a), -2.0);
+        assert_eq!(a.perp(), v2!(0.0, 1.0));
+    }
+
+    #[test]
+    fn mag_and_lerp() {
+        let a = v2!(3.0, 4.0);
+        assert_eq!(a.mag(), 5.0);
+        assert_eq!(a.normalize(), v2!(0.6, 0.8));
+        assert_eq!(v2!(0.0, 0.0).lerp(&a, 0.5), v2!(1.5, 2.0));
+        assert_eq!(a.distance(&v2!(0.0, 0.0)), 5.0);
+    }
+}
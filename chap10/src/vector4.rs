@@ -0,0 +1,210 @@
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use crate::{v3, vector::V3};
+
+#[macro_export]
+macro_rules! v4 {
+    ($x:expr, $y:expr, $z:expr, $w:expr $(,)?) => {
+        V4::new($x, $y, $z, $w)
+    };
+}
+
+// 同次座標のベクトル。点はw = 1、方向はw = 0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct V4 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub w: f64,
+}
+
+impl V4 {
+    pub fn new(x: f64, y: f64, z: f64, w: f64) -> Self {
+        V4 { x, y, z, w }
+    }
+
+    pub fn from_point(p: V3) -> Self {
+        V4::new(p.x, p.y, p.z, 1.0)
+    }
+
+    // 平行移動の影響を受けない
+    pub fn from_direction(d: V3) -> Self {
+        V4::new(d.x, d.y, d.z, 0.0)
+    }
+
+    pub fn xyz(&self) -> V3 {
+        v3!(self.x, self.y, self.z)
+    }
+
+    // wで割って3次元に戻す。w = 0 の方向ベクトルはそのままxyzを返す
+    pub fn perspective_divide(&self) -> V3 {
+        if self.w == 0.0 {
+            return self.xyz();
+        }
+
+        self.xyz() / self.w
+    }
+
+    pub fn mag(&self) -> f64 {
+        self.mag_squared().sqrt()
+    }
+
+    pub fn mag_squared(&self) -> f64 {
+        self.dot(self)
+    }
+
+    pub fn normalize(&self) -> Self {
+        let mag = self.mag();
+
+        *self / mag
+    }
+
+    pub fn dot(&self, rhs: &Self) -> f64 {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z + self.w * rhs.w
+    }
+
+    pub fn lerp(&self, rhs: &Self, t: f64) -> Self {
+        *self + (*rhs - *self) * t
+    }
+}
+
+impl Add for V4 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        V4 {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+            z: self.z + rhs.z,
+            w: self.w + rhs.w,
+        }
+    }
+}
+
+impl AddAssign for V4 {
+    fn add_assign(&mut self, rhs: Self) {
+        self.x += rhs.x;
+        self.y += rhs.y;
+        self.z += rhs.z;
+        self.w += rhs.w;
+    }
+}
+
+impl Sub for V4 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        V4 {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+            z: self.z - rhs.z,
+            w: self.w - rhs.w,
+        }
+    }
+}
+
+impl SubAssign for V4 {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.x -= rhs.x;
+        self.y -= rhs.y;
+        self.z -= rhs.z;
+        self.w -= rhs.w;
+    }
+}
+
+impl Neg for V4 {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        V4 {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+            w: -self.w,
+        }
+    }
+}
+
+impl Mul<f64> for V4 {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        V4 {
+            x: self.x * rhs,
+            y: self.y * rhs,
+            z: self.z * rhs,
+            w: self.w * rhs,
+        }
+    }
+}
+
+impl MulAssign<f64> for V4 {
+    fn mul_assign(&mut self, rhs: f64) {
+        self.x *= rhs;
+        self.y *= rhs;
+        self.z *= rhs;
+        self.w *= rhs;
+    }
+}
+
+impl Div<f64> for V4 {
+    type Output = Self;
+
+    fn div(self, rhs: f64) -> Self::Output {
+        V4 {
+            x: self.x / rhs,
+            y: self.y / rhs,
+            z: self.z / rhs,
+            w: self.w / rhs,
+        }
+    }
+}
+
+impl DivAssign<f64> for V4 {
+    fn div_assign(&mut self, rhs: f64) {
+        self.x /= rhs;
+        self.y /= rhs;
+        self.z /= rhs;
+        self.w /= rhs;
+    }
+}
+
+impl Mul<V4> for f64 {
+    type Output = V4;
+
+    fn mul(self, rhs: V4) -> Self::Output {
+        V4 {
+            x: self * rhs.x,
+            y: self * rhs.y,
+            z: self * rhs.z,
+            w: self * rhs.w,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        let a = v4!(1.0, 2.0, 3.0, 4.0);
+        let b = v4!(4.0, 3.0, 2.0, 1.0);
+        assert_eq!(a + b, v4!(5.0, 5.0, 5.0, 5.0));
+        assert_eq!(a - b, v4!(-3.0, -1.0, 1.0, 3.0));
+        assert_eq!(-a, v4!(-1.0, -2.0, -3.0, -4.0));
+        assert_eq!(2.0 * a, a * 2.0);
+        assert_eq!(a.dot(&b), 20.0);
+        assert_eq!(v4!(0.0, 0.0, 3.0, 4.0).mag(), 5.0);
+    }
+
+    #[test]
+    fn homogeneous() {
+        let p = v3!(1.0, 2.0, 3.0);
+        assert_eq!(V4::from_point(p).w, 1.0);
+        assert_eq!(V4::from_direction(p).w, 0.0);
+        assert_eq!(V4::from_point(p).perspective_divide(), p);
+        assert_eq!(v4!(2.0, 4.0, 6.0, 2.0).perspective_divide(), p);
+        assert_eq!(V4::from_direction(p).perspective_divide(), p);
+    }
+}